@@ -0,0 +1,127 @@
+//! Note aliases: alternative names from frontmatter `aliases: [...]` that
+//! wiki links can use instead of the note's file name
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::{frontmatter, get_modified_time, FsResult, NoteIndex};
+
+/// Aliases declared in a note's frontmatter (`aliases:` or `alias:`, as a
+/// list or a single value)
+pub(crate) fn note_aliases(content: &str) -> Vec<String> {
+    let fm = match frontmatter::parse_frontmatter(content) {
+        Some(fm) => fm,
+        None => return Vec::new(),
+    };
+
+    let mut aliases: Vec<String> = Vec::new();
+    for key in ["aliases", "alias"] {
+        for alias in fm.get(key).map(|v| v.as_list()).unwrap_or_default() {
+            let alias = alias.trim().to_string();
+            if !alias.is_empty() && !aliases.iter().any(|a| a.eq_ignore_ascii_case(&alias)) {
+                aliases.push(alias);
+            }
+        }
+    }
+    aliases
+}
+
+/// An alias that does not lead to a single note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AliasCollision {
+    pub alias: String,
+    /// Notes declaring the alias
+    pub alias_notes: Vec<String>,
+    /// Notes whose name is the alias; links using it go to these instead
+    pub name_notes: Vec<String>,
+}
+
+/// List aliases that clash with a note name or are declared by several
+/// notes - Tauri command
+///
+/// A note name always wins over an alias, so `[[alias]]` never reaches the
+/// aliased note when another note has that name. An alias shared by several
+/// notes resolves like a duplicate name (see `resolve_wiki_link`).
+#[tauri::command]
+pub fn get_alias_collisions(vault_path: String) -> FsResult<Vec<AliasCollision>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let path_of = |i: usize| index.notes[i].to_string_lossy().to_string();
+    let mut collisions: BTreeMap<String, AliasCollision> = BTreeMap::new();
+
+    for i in 0..index.notes.len() {
+        for alias in index.aliases(i) {
+            // Naming a note after itself is harmless
+            if alias.eq_ignore_ascii_case(&index.name(i)) {
+                continue;
+            }
+            collisions
+                .entry(alias.to_lowercase())
+                .or_insert_with(|| AliasCollision {
+                    alias: alias.clone(),
+                    alias_notes: Vec::new(),
+                    name_notes: index.named(alias).into_iter().map(path_of).collect(),
+                })
+                .alias_notes
+                .push(path_of(i));
+        }
+    }
+
+    collisions.retain(|_, c| !c.name_notes.is_empty() || c.alias_notes.len() > 1);
+    FsResult::ok(collisions.into_values().collect())
+}
+
+/// A quick-open entry: a note, or one of its aliases
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuickOpenItem {
+    /// Text to match against: the note name or the alias
+    pub label: String,
+    /// The note's name
+    pub name: String,
+    pub path: String,
+    /// Path relative to the vault (e.g., "folder/note.md")
+    pub relative_path: String,
+    /// Set when `label` is an alias of the note
+    pub is_alias: bool,
+    pub modified: u64,
+}
+
+/// List every note in the vault plus one entry per alias, for quick-open -
+/// Tauri command
+#[tauri::command]
+pub fn get_quick_open_items(vault_path: String) -> FsResult<Vec<QuickOpenItem>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::ok(vec![]);
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut items = Vec::new();
+
+    for (i, path) in index.notes.iter().enumerate() {
+        let name = index.name(i);
+        let labels = std::iter::once((name.clone(), false))
+            .chain(index.aliases(i).iter().map(|a| (a.clone(), true)));
+
+        for (label, is_alias) in labels {
+            items.push(QuickOpenItem {
+                label,
+                name: name.clone(),
+                path: path.to_string_lossy().to_string(),
+                relative_path: index.relative_path(i),
+                is_alias,
+                modified: get_modified_time(path),
+            });
+        }
+    }
+
+    items.sort_by_key(|item| (item.label.to_lowercase(), item.is_alias, item.path.clone()));
+    FsResult::ok(items)
+}
@@ -0,0 +1,317 @@
+//! Attachment report: cross-references attachment files against note links
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::history::Journal;
+use crate::{
+    collect_vault_files, current_timestamp, decode_percent, get_modified_time,
+    markdown_link_matches, normalize_path, parse_wiki_links, FsResult,
+};
+
+/// Vault-local folder that removed files are moved into (hidden, so never scanned)
+pub const TRASH_DIR: &str = ".trash";
+
+/// An attachment file (any non-markdown file) in the vault
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttachmentInfo {
+    pub name: String,
+    pub path: String,
+    pub relative_path: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// A link from a note to an attachment file that does not exist
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MissingAttachment {
+    /// Path to the note containing the link
+    pub source_path: String,
+    /// Name of the note containing the link
+    pub source_name: String,
+    /// The link target as written in the note
+    pub target: String,
+    /// Start position in the note content (byte offset)
+    pub start: usize,
+    /// End position in the note content (byte offset)
+    pub end: usize,
+    /// The raw matched link text
+    pub raw: String,
+}
+
+/// Result of cross-referencing attachments against links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentReport {
+    pub total_attachments: usize,
+    /// Attachments that no note links to
+    pub unused: Vec<AttachmentInfo>,
+    /// Links that point to attachments that do not exist
+    pub missing: Vec<MissingAttachment>,
+}
+
+/// A file moved into the vault trash
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedFile {
+    pub original_path: String,
+    pub trash_path: String,
+}
+
+/// A link to a (possible) attachment found in note content
+pub(crate) struct AttachmentRef {
+    pub target: String,
+    pub start: usize,
+    pub end: usize,
+    pub raw: String,
+}
+
+/// Check whether a link target names a non-markdown file (e.g., "image.png")
+pub(crate) fn looks_like_attachment(target: &str) -> bool {
+    match Path::new(target).extension() {
+        Some(ext) => {
+            let ext = ext.to_string_lossy();
+            !ext.eq_ignore_ascii_case("md")
+                && !ext.is_empty()
+                && ext.len() <= 5
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    }
+}
+
+/// Extract attachment links from note content: `[[file.ext]]`, `![[file.ext]]`,
+/// `![alt](path)` and `[text](path)` pointing at local non-markdown files,
+/// outside code and HTML comments
+pub(crate) fn parse_attachment_refs(content: &str) -> Vec<AttachmentRef> {
+    let mut refs: Vec<AttachmentRef> = parse_wiki_links(content)
+        .into_iter()
+        .filter(|l| looks_like_attachment(&l.target))
+        .map(|l| AttachmentRef {
+            target: l.target,
+            start: l.start,
+            end: l.end,
+            raw: l.raw,
+        })
+        .collect();
+
+    for m in markdown_link_matches(content) {
+        let target = decode_percent(m.url);
+        if !looks_like_attachment(&target) {
+            continue;
+        }
+
+        refs.push(AttachmentRef {
+            target,
+            start: m.start,
+            end: m.end,
+            raw: m.raw.to_string(),
+        });
+    }
+
+    refs.sort_by_key(|r| r.start);
+    refs
+}
+
+/// Resolve an attachment link relative to the linking note, then the vault root,
+/// then by bare file name anywhere in the vault
+pub(crate) fn resolve_attachment(
+    vault: &Path,
+    note_dir: &Path,
+    target: &str,
+    by_name: &HashMap<String, Vec<PathBuf>>,
+) -> Option<PathBuf> {
+    let relative = target.trim_start_matches('/');
+
+    for base in [note_dir, vault] {
+        let candidate = normalize_path(&base.join(relative));
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    if !target.contains('/') {
+        if let Some(candidates) = by_name.get(&target.to_lowercase()) {
+            return candidates.first().cloned();
+        }
+    }
+
+    None
+}
+
+/// Index files by lowercase file name for bare-name lookups
+pub(crate) fn index_by_file_name(files: &[PathBuf]) -> HashMap<String, Vec<PathBuf>> {
+    let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for file in files {
+        if let Some(name) = file.file_name() {
+            by_name
+                .entry(name.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(normalize_path(file));
+        }
+    }
+    by_name
+}
+
+/// Build the attachment report for a vault
+fn build_attachment_report(vault: &PathBuf) -> AttachmentReport {
+    let files = collect_vault_files(vault);
+    let by_name = index_by_file_name(&files);
+
+    let (notes, attachments): (Vec<PathBuf>, Vec<PathBuf>) = files
+        .into_iter()
+        .partition(|p| p.extension().is_some_and(|ext| ext == "md"));
+
+    let mut used: HashSet<PathBuf> = HashSet::new();
+    let mut missing: Vec<MissingAttachment> = Vec::new();
+
+    for note_path in &notes {
+        let content = match fs::read_to_string(note_path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        let note_dir = note_path.parent().unwrap_or(vault);
+        let source_name = note_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        for r in parse_attachment_refs(&content) {
+            // A wiki link like [[v1.2]] may name a note rather than a file
+            if by_name.contains_key(&format!("{}.md", r.target.to_lowercase())) {
+                continue;
+            }
+
+            match resolve_attachment(vault, note_dir, &r.target, &by_name) {
+                Some(path) => {
+                    used.insert(path);
+                }
+                None => missing.push(MissingAttachment {
+                    source_path: note_path.to_string_lossy().to_string(),
+                    source_name: source_name.clone(),
+                    target: r.target,
+                    start: r.start,
+                    end: r.end,
+                    raw: r.raw,
+                }),
+            }
+        }
+    }
+
+    let total_attachments = attachments.len();
+    let unused: Vec<AttachmentInfo> = attachments
+        .into_iter()
+        .filter(|p| !used.contains(&normalize_path(p)))
+        .map(|p| AttachmentInfo {
+            name: p
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            relative_path: p
+                .strip_prefix(vault)
+                .unwrap_or(&p)
+                .to_string_lossy()
+                .to_string(),
+            size: fs::metadata(&p).map(|m| m.len()).unwrap_or(0),
+            modified: get_modified_time(&p),
+            path: p.to_string_lossy().to_string(),
+        })
+        .collect();
+
+    AttachmentReport {
+        total_attachments,
+        unused,
+        missing,
+    }
+}
+
+/// Report unused attachments and links to missing attachments - Tauri command
+#[tauri::command]
+pub fn get_attachment_report(vault_path: String) -> FsResult<AttachmentReport> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    FsResult::ok(build_attachment_report(&vault))
+}
+
+/// Move a file into the vault trash, keeping its relative path
+pub(crate) fn move_to_trash(
+    vault: &Path,
+    path: &Path,
+    journal: &mut Journal,
+) -> std::io::Result<PathBuf> {
+    let relative = path.strip_prefix(vault).unwrap_or(path);
+    let mut dest = vault.join(TRASH_DIR).join(relative);
+
+    // Never overwrite something already in the trash
+    if dest.exists() {
+        let stem = dest
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file_name = match dest.extension() {
+            Some(ext) => format!("{} {}.{}", stem, current_timestamp(), ext.to_string_lossy()),
+            None => format!("{} {}", stem, current_timestamp()),
+        };
+        dest.set_file_name(file_name);
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    journal.rename(path, &dest)?;
+    Ok(dest)
+}
+
+/// Move unused attachments into the vault's `.trash` folder - Tauri command
+///
+/// When `paths` is given only those attachments are trashed, and only if they
+/// are still unused; otherwise every unused attachment is trashed.
+#[tauri::command]
+pub fn trash_unused_attachments(
+    vault_path: String,
+    paths: Option<Vec<String>>,
+) -> FsResult<Vec<TrashedFile>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    // Recompute the report so files referenced since it was shown are kept
+    let report = build_attachment_report(&vault);
+    let selected: Option<HashSet<PathBuf>> =
+        paths.map(|list| list.iter().map(|p| normalize_path(Path::new(p))).collect());
+
+    let mut journal = Journal::new(Some(vault.clone()), "Trash unused attachments");
+    let mut trashed = Vec::new();
+    for attachment in report.unused {
+        let path = PathBuf::from(&attachment.path);
+        if let Some(selected) = &selected {
+            if !selected.contains(&normalize_path(&path)) {
+                continue;
+            }
+        }
+
+        match move_to_trash(&vault, &path, &mut journal) {
+            Ok(dest) => trashed.push(TrashedFile {
+                original_path: attachment.path,
+                trash_path: dest.to_string_lossy().to_string(),
+            }),
+            Err(e) => {
+                return FsResult::err(&format!(
+                    "Failed to trash {}: {}",
+                    attachment.relative_path, e
+                ))
+            }
+        }
+    }
+
+    if let Err(e) = journal.finish() {
+        return FsResult::err(&format!("Trashed, but failed to save history: {}", e));
+    }
+    FsResult::ok(trashed)
+}
@@ -0,0 +1,274 @@
+//! Block references: `^block-id` markers and `[[note#^block-id]]` links
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::history::{vault_for, Journal};
+use crate::{fenced_line_mask, FsResult};
+
+/// A block (paragraph, list item, ...) marked with a `^block-id`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Block {
+    /// The block ID without the `^`
+    pub id: String,
+    /// Line number of the `^block-id` marker (1-based)
+    pub line: usize,
+    /// First line of the block (1-based)
+    pub start_line: usize,
+    /// Byte offset of the start of the block
+    pub start: usize,
+    /// Byte offset of the end of the block (before the final line break)
+    pub end: usize,
+    /// Block text without the marker
+    pub text: String,
+}
+
+/// A line of content with its byte range (line break excluded)
+struct Line<'a> {
+    text: &'a str,
+    start: usize,
+    in_code: bool,
+}
+
+fn split_lines(content: &str) -> Vec<Line<'_>> {
+    let in_code = fenced_line_mask(content);
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    for (i, text) in content.split('\n').enumerate() {
+        lines.push(Line {
+            text: text.trim_end_matches('\r'),
+            start: offset,
+            in_code: in_code.get(i).copied().unwrap_or(false),
+        });
+        offset += text.len() + 1;
+    }
+    lines
+}
+
+fn block_marker_regex() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| Regex::new(r"(?:^|\s)\^([A-Za-z0-9-]+)$").unwrap())
+}
+
+fn is_list_item(line: &str) -> bool {
+    static LIST_ITEM: OnceLock<Regex> = OnceLock::new();
+    let re = LIST_ITEM.get_or_init(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s").unwrap());
+    re.is_match(line)
+}
+
+fn is_heading(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// First line (index) of the block that ends on line `end`: a list item is
+/// its own block, a paragraph runs back to the previous blank line or heading
+fn block_start(lines: &[Line], end: usize) -> usize {
+    if is_list_item(lines[end].text) || is_heading(lines[end].text) {
+        return end;
+    }
+
+    let mut start = end;
+    while start > 0 {
+        let previous = &lines[start - 1];
+        if previous.text.trim().is_empty()
+            || previous.in_code
+            || is_heading(previous.text)
+            || is_list_item(previous.text)
+        {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+/// Last line (index) of the block containing line `index`: a paragraph or
+/// list item runs on to the next blank line, heading, list item or code
+fn block_end(lines: &[Line], index: usize) -> usize {
+    if is_heading(lines[index].text) {
+        return index;
+    }
+
+    let mut end = index;
+    while end + 1 < lines.len() {
+        let next = &lines[end + 1];
+        if next.text.trim().is_empty()
+            || next.in_code
+            || is_heading(next.text)
+            || is_list_item(next.text)
+        {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// Byte range of the paragraph or list item (with its continuation lines)
+/// containing `offset`, line break excluded
+pub(crate) fn block_range(content: &str, offset: usize) -> (usize, usize) {
+    let lines = split_lines(content);
+    let index = lines.iter().rposition(|l| l.start <= offset).unwrap_or(0);
+
+    let mut first = block_start(&lines, index);
+    if first > 0 && !is_list_item(lines[first].text) && is_list_item(lines[first - 1].text) {
+        first -= 1;
+    }
+    let last = block_end(&lines, index);
+    (
+        lines[first].start,
+        lines[last].start + lines[last].text.len(),
+    )
+}
+
+/// Parse every `^block-id` marker in note content, outside fenced code.
+///
+/// A marker at the end of a line labels that line's block; a marker alone on
+/// a line labels the block just above it (e.g., a list, table or code block).
+pub(crate) fn parse_blocks(content: &str) -> Vec<Block> {
+    let re = block_marker_regex();
+    let lines = split_lines(content);
+    let mut blocks = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.in_code {
+            continue;
+        }
+        let trimmed = line.text.trim_end();
+        let cap = match re.captures(trimmed) {
+            Some(cap) => cap,
+            None => continue,
+        };
+        let id = cap[1].to_string();
+        let marker_start = cap.get(0).map_or(0, |m| m.start());
+
+        let (first, last, last_text) = if trimmed[..marker_start].trim().is_empty() {
+            // Standalone marker: the block above, up to the previous blank line
+            if i == 0 || lines[i - 1].text.trim().is_empty() {
+                continue;
+            }
+            let mut first = i - 1;
+            while first > 0 && !lines[first - 1].text.trim().is_empty() {
+                first -= 1;
+            }
+            (first, i - 1, lines[i - 1].text)
+        } else {
+            (
+                block_start(&lines, i),
+                i,
+                trimmed[..marker_start].trim_end(),
+            )
+        };
+
+        let mut text: Vec<&str> = lines[first..last].iter().map(|l| l.text).collect();
+        text.push(last_text);
+
+        blocks.push(Block {
+            id,
+            line: i + 1,
+            start_line: first + 1,
+            start: lines[first].start,
+            end: lines[last].start + lines[last].text.len(),
+            text: text.join("\n"),
+        });
+    }
+    blocks
+}
+
+/// Find a block by ID (case-insensitive, with or without the leading `^`)
+pub(crate) fn find_block<'a>(blocks: &'a [Block], id: &str) -> Option<&'a Block> {
+    let id = id.trim().trim_start_matches('^');
+    blocks.iter().find(|b| b.id.eq_ignore_ascii_case(id))
+}
+
+/// Generate a random lowercase alphanumeric ID of `len` characters (at most
+/// twelve) for which `taken` is false
+pub(crate) fn unique_id(len: usize, seed: impl Hash, taken: impl Fn(&str) -> bool) -> String {
+    const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    let mut attempt = 0u32;
+    loop {
+        let mut hasher = DefaultHasher::new();
+        (nanos, &seed, attempt).hash(&mut hasher);
+        let mut value = hasher.finish();
+
+        let id: String = (0..len)
+            .map(|_| {
+                let c = ALPHABET[(value % ALPHABET.len() as u64) as usize] as char;
+                value /= ALPHABET.len() as u64;
+                c
+            })
+            .collect();
+
+        if !taken(&id) {
+            return id;
+        }
+        attempt = attempt.wrapping_add(1);
+    }
+}
+
+/// Get or create the block ID for the block containing `line` - Tauri command
+///
+/// If the block already has an ID it is returned unchanged; otherwise a new
+/// `^id` marker is appended to the block's last line.
+#[tauri::command]
+pub fn generate_block_id(path: String, line: usize) -> FsResult<Block> {
+    let note_path = PathBuf::from(&path);
+    let content = match fs::read_to_string(&note_path) {
+        Ok(c) => c,
+        Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+    };
+
+    let lines = split_lines(&content);
+    let index = match line.checked_sub(1).filter(|i| *i < lines.len()) {
+        Some(i) => i,
+        None => return FsResult::err("Line is out of range"),
+    };
+    if lines[index].text.trim().is_empty() {
+        return FsResult::err("Cannot add a block ID to an empty line");
+    }
+    if lines[index].in_code {
+        return FsResult::err("Cannot add a block ID inside a code block");
+    }
+
+    let blocks = parse_blocks(&content);
+    if let Some(block) = blocks
+        .iter()
+        .find(|b| b.start_line <= line && line <= b.line)
+    {
+        return FsResult::ok(block.clone());
+    }
+
+    // A paragraph's marker goes on its last line; list items and headings
+    // are blocks of their own
+    let last = if is_list_item(lines[index].text) {
+        index
+    } else {
+        block_end(&lines, index)
+    };
+
+    let id = unique_id(6, line, |id| find_block(&blocks, id).is_some());
+    let insert_at = lines[last].start + lines[last].text.trim_end().len();
+    let updated = format!("{} ^{}{}", &content[..insert_at], id, &content[insert_at..]);
+
+    let journal = Journal::new(vault_for(None, &note_path), format!("Add block ID ^{}", id));
+    if let Err(e) = journal.finish_write(&note_path, &content, &updated) {
+        return FsResult::err(&e);
+    }
+
+    match parse_blocks(&updated).into_iter().find(|b| b.id == id) {
+        Some(block) => FsResult::ok(block),
+        None => FsResult::err("Failed to add block ID"),
+    }
+}
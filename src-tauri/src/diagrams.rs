@@ -0,0 +1,421 @@
+//! Diagram code blocks (`mermaid`, `dot`) rendered to inline SVG
+//!
+//! Graphviz `dot` goes through layout-rs directly. Mermaid flowcharts are
+//! translated into a layout-rs graph; other Mermaid diagram types are
+//! reported as unsupported.
+
+use layout::backends::svg::SVGWriter;
+use layout::core::base::Orientation;
+use layout::core::style::{LineStyleKind, StyleAttr};
+use layout::gv::{DotParser, GraphBuilder, Lexer, Token};
+use layout::std_shapes::render::get_shape_size;
+use layout::std_shapes::shapes::{Arrow, Element, LineEndKind, ShapeKind};
+use layout::topo::layout::VisualGraph;
+use regex::Regex;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::OnceLock;
+
+/// Font size for Mermaid node and edge labels, in pixels
+const FONT_SIZE: usize = 15;
+
+/// Why a diagram could not be rendered
+#[derive(Debug, Clone)]
+pub(crate) struct DiagramError {
+    pub message: String,
+    /// Line in the diagram source (1-based), when known
+    pub line: Option<usize>,
+}
+
+impl DiagramError {
+    fn new(message: impl Into<String>, line: Option<usize>) -> DiagramError {
+        DiagramError {
+            message: message.into(),
+            line,
+        }
+    }
+}
+
+/// Whether a fenced code block language is a diagram
+pub(crate) fn is_diagram_language(lang: &str) -> bool {
+    matches!(lang.to_lowercase().as_str(), "mermaid" | "dot" | "graphviz")
+}
+
+/// Render a diagram code block to an `<svg>` element. `id_prefix` keeps the
+/// element IDs of several diagrams on one page apart.
+pub(crate) fn render_diagram(
+    lang: &str,
+    source: &str,
+    id_prefix: &str,
+) -> Result<String, DiagramError> {
+    // layout-rs panics on some inputs instead of returning an error
+    let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut graph = match lang.to_lowercase().as_str() {
+            "mermaid" => mermaid_graph(source)?,
+            _ => dot_graph(source)?,
+        };
+        if graph.num_nodes() == 0 {
+            return Err(DiagramError::new("The diagram has no nodes", None));
+        }
+        let mut svg = SVGWriter::new();
+        graph.do_it(false, false, false, &mut svg);
+        Ok(svg.finalize())
+    }));
+
+    match rendered {
+        Ok(Ok(svg)) => Ok(clean_svg(&svg, id_prefix)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(DiagramError::new("The diagram could not be laid out", None)),
+    }
+}
+
+/// Make layout-rs output embeddable: drop the XML declaration, turn the
+/// `<style>` font classes into attributes and prefix element IDs
+fn clean_svg(svg: &str, id_prefix: &str) -> String {
+    static STYLE: OnceLock<Regex> = OnceLock::new();
+    static FONT: OnceLock<Regex> = OnceLock::new();
+    static IDS: OnceLock<Regex> = OnceLock::new();
+    let style = STYLE.get_or_init(|| Regex::new(r"(?s)<\?xml[^>]*\?>|<style>.*?</style>").unwrap());
+    let font = FONT.get_or_init(|| Regex::new(r#"class="a(\d+)""#).unwrap());
+    let ids = IDS.get_or_init(|| Regex::new(r##"(id="|href="#|url\(#)([^"#)]+)"##).unwrap());
+
+    let svg = style.replace_all(svg, "");
+    let svg = font.replace_all(&svg, r#"font-size="$1" font-family="sans-serif""#);
+    let svg = ids.replace_all(&svg, |caps: &regex::Captures| {
+        format!("{}{}{}", &caps[1], id_prefix, &caps[2])
+    });
+    svg.trim().to_string()
+}
+
+fn dot_graph(source: &str) -> Result<VisualGraph, DiagramError> {
+    let graph = DotParser::new(source).process().map_err(|e| {
+        DiagramError::new(
+            format!("Invalid dot graph: {}", e),
+            Some(dot_error_line(source)),
+        )
+    })?;
+    let mut builder = GraphBuilder::new();
+    builder.visit_graph(&graph);
+    Ok(builder.get())
+}
+
+/// Text that completes a dot graph after most tokens, before closing the
+/// open brackets and braces
+const DOT_COMPLETIONS: &[&str] = &["", " x", " = x", " x = x", " []", " {}"];
+
+/// Line of the token a dot parse error is at.
+///
+/// DotParser does not report where it failed, but it reads the source token
+/// by token and stops at the first one that cannot continue a graph. That is
+/// the first token after which no completion of the source parses.
+fn dot_error_line(source: &str) -> usize {
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexer = Lexer::from_string(source);
+    // The lexer has read one character past the token
+    let token_end = |lexer: &Lexer| match lexer.ch {
+        '\0' => chars.len(),
+        _ => lexer.pos - 1,
+    };
+    let mut depth = 0;
+    let mut in_brackets = false;
+
+    loop {
+        let token = lexer.next_token();
+        let end = token_end(&lexer);
+        match token {
+            // The source ended early, or this token is not valid dot
+            Token::EOF | Token::Error(_) => break,
+            Token::OpenBrace => depth += 1,
+            Token::CloseBrace => depth -= 1,
+            Token::OpenBracket => in_brackets = true,
+            Token::CloseBracket => in_brackets = false,
+            _ => {}
+        }
+
+        let prefix: String = chars[..end].iter().collect();
+        let closing = format!(
+            "{}{}",
+            if in_brackets { "]" } else { "" },
+            "}".repeat(depth.max(0) as usize)
+        );
+        let viable = DOT_COMPLETIONS.iter().any(|completion| {
+            let graph = format!("{}{}{}", prefix, completion, closing);
+            panic::catch_unwind(|| DotParser::new(&graph).process().is_ok()).unwrap_or(false)
+        });
+        if !viable {
+            break;
+        }
+    }
+
+    let end = token_end(&lexer);
+    chars[..end.saturating_sub(1)]
+        .iter()
+        .filter(|&&c| c == '\n')
+        .count()
+        + 1
+}
+
+/// Node shape brackets: opening, closing, circle
+const MERMAID_SHAPES: &[(&str, &str, bool)] = &[
+    ("(((", ")))", true),
+    ("((", "))", true),
+    ("([", "])", false),
+    ("[[", "]]", false),
+    ("[(", ")]", false),
+    ("{{", "}}", false),
+    ("[/", "/]", false),
+    ("[\\", "\\]", false),
+    ("[", "]", false),
+    ("(", ")", false),
+    ("{", "}", false),
+    (">", "]", false),
+];
+
+/// A node reference in a flowchart statement
+struct MermaidNode {
+    id: String,
+    label: Option<String>,
+    circle: bool,
+    rounded: bool,
+}
+
+/// An edge between node groups
+struct MermaidEdge {
+    label: String,
+    start: LineEndKind,
+    end: LineEndKind,
+    style: LineStyleKind,
+    thick: bool,
+}
+
+/// Split a statement into `A & B`, edge, `C`, edge, ... parts
+fn parse_mermaid_statement(
+    statement: &str,
+    line: usize,
+) -> Result<(Vec<Vec<MermaidNode>>, Vec<MermaidEdge>), DiagramError> {
+    static ID: OnceLock<Regex> = OnceLock::new();
+    static TEXT_EDGE: OnceLock<Regex> = OnceLock::new();
+    static EDGE: OnceLock<Regex> = OnceLock::new();
+    static PIPE_LABEL: OnceLock<Regex> = OnceLock::new();
+    let id_re = ID.get_or_init(|| Regex::new(r"^\w+").unwrap());
+    let text_edge = TEXT_EDGE.get_or_init(|| {
+        Regex::new(r"^(<?)(--|==|-\.)\s*([^-=.>|][^>|]*?)\s*(-->|---|==>|===|\.->|\.-)").unwrap()
+    });
+    let edge_re = EDGE.get_or_init(|| {
+        Regex::new(r"^(<|o|x)?(-{2,}>|-{3,}|-\.+->|-\.+-|={2,}>|={3,}|--o|--x|~~~)").unwrap()
+    });
+    let pipe_label = PIPE_LABEL.get_or_init(|| Regex::new(r"^\|([^|]*)\|").unwrap());
+
+    let error = |message: String| DiagramError::new(message, Some(line));
+    let mut groups: Vec<Vec<MermaidNode>> = Vec::new();
+    let mut edges: Vec<MermaidEdge> = Vec::new();
+    let mut rest = statement.trim();
+
+    loop {
+        // One or more nodes joined with `&`
+        let mut group = Vec::new();
+        loop {
+            let id = match id_re.find(rest) {
+                Some(m) => m.as_str().to_string(),
+                None => return Err(error(format!("Expected a node at \"{}\"", rest))),
+            };
+            rest = &rest[id.len()..];
+
+            let mut node = MermaidNode {
+                id,
+                label: None,
+                circle: false,
+                rounded: false,
+            };
+            if let Some((open, close, circle)) = MERMAID_SHAPES
+                .iter()
+                .find(|(open, _, _)| rest.starts_with(open))
+            {
+                let inner = &rest[open.len()..];
+                let end = inner
+                    .find(close)
+                    .ok_or_else(|| error(format!("Missing {} in node {}", close, node.id)))?;
+                let label = inner[..end].trim().trim_matches('"').trim();
+                node.label = Some(label.replace("<br>", "\n").replace("<br/>", "\n"));
+                node.circle = *circle;
+                node.rounded = open.starts_with('(') && !circle;
+                rest = &inner[end + close.len()..];
+            }
+            group.push(node);
+
+            rest = rest.trim_start();
+            match rest.strip_prefix('&') {
+                Some(after) => rest = after.trim_start(),
+                None => break,
+            }
+        }
+        groups.push(group);
+
+        if rest.is_empty() {
+            break;
+        }
+
+        // An edge, with an optional `-- text -->` or `-->|text|` label
+        let (arrow, mut label, after) = if let Some(caps) = text_edge.captures(rest) {
+            let whole = caps.get(0).map_or(0, |m| m.end());
+            let arrow = format!("{}{}", &caps[1], &caps[4]);
+            (arrow, caps[3].trim().to_string(), &rest[whole..])
+        } else if let Some(m) = edge_re.find(rest) {
+            (m.as_str().to_string(), String::new(), &rest[m.end()..])
+        } else {
+            return Err(error(format!("Expected an edge at \"{}\"", rest)));
+        };
+        rest = after.trim_start();
+        if let Some(caps) = pipe_label.captures(rest) {
+            label = caps[1].trim().trim_matches('"').to_string();
+            rest = rest[caps.get(0).map_or(0, |m| m.end())..].trim_start();
+        }
+
+        let head = |c: char| {
+            if c == '>' {
+                LineEndKind::Arrow
+            } else {
+                LineEndKind::None
+            }
+        };
+        edges.push(MermaidEdge {
+            label,
+            start: if arrow.starts_with('<') {
+                LineEndKind::Arrow
+            } else {
+                LineEndKind::None
+            },
+            end: head(arrow.chars().last().unwrap_or('-')),
+            style: if arrow.contains('.') {
+                LineStyleKind::Dashed
+            } else if arrow == "~~~" {
+                LineStyleKind::None
+            } else {
+                LineStyleKind::Normal
+            },
+            thick: arrow.contains('='),
+        });
+
+        if rest.is_empty() {
+            return Err(error("Edge has no target node".to_string()));
+        }
+    }
+
+    Ok((groups, edges))
+}
+
+/// Translate a Mermaid flowchart into a layout-rs graph
+fn mermaid_graph(source: &str) -> Result<VisualGraph, DiagramError> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static IGNORED: OnceLock<Regex> = OnceLock::new();
+    let header = HEADER
+        .get_or_init(|| Regex::new(r"^(graph|flowchart)(?:\s+(TB|TD|BT|LR|RL))?\s*;?$").unwrap());
+    let ignored = IGNORED.get_or_init(|| {
+        Regex::new(r"^(subgraph\b|end$|direction\b|classDef\b|class\b|style\b|linkStyle\b|click\b)")
+            .unwrap()
+    });
+
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with("%%"));
+
+    let (first_line, first) = lines
+        .next()
+        .ok_or_else(|| DiagramError::new("The diagram is empty", None))?;
+    let caps = match header.captures(first) {
+        Some(caps) => caps,
+        None => {
+            let kind = first.split_whitespace().next().unwrap_or(first);
+            return Err(DiagramError::new(
+                format!(
+                    "Unsupported Mermaid diagram type \"{}\"; only flowcharts can be rendered",
+                    kind
+                ),
+                Some(first_line),
+            ));
+        }
+    };
+    let orientation = match caps.get(2).map(|m| m.as_str()) {
+        Some("LR" | "RL") => Orientation::LeftToRight,
+        _ => Orientation::TopToBottom,
+    };
+
+    let mut graph = VisualGraph::new(orientation);
+    let mut handles = HashMap::new();
+    let mut labels: HashMap<String, (String, bool, bool)> = HashMap::new();
+    let mut statements = Vec::new();
+
+    for (line, text) in lines {
+        for statement in text.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            if ignored.is_match(statement) {
+                continue;
+            }
+            let (groups, edges) = parse_mermaid_statement(statement, line)?;
+            // As in Mermaid, the last labelled mention of a node sets its label
+            // and shape
+            for node in groups.iter().flatten() {
+                let entry = labels
+                    .entry(node.id.clone())
+                    .or_insert_with(|| (node.id.clone(), false, false));
+                if let Some(label) = &node.label {
+                    *entry = (label.clone(), node.circle, node.rounded);
+                }
+            }
+            statements.push((groups, edges));
+        }
+    }
+
+    let mut handle_of = |graph: &mut VisualGraph, id: &str| {
+        *handles.entry(id.to_string()).or_insert_with(|| {
+            let (label, circle, rounded) = labels
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| (id.to_string(), false, false));
+            let shape = if circle {
+                ShapeKind::new_circle(&label)
+            } else {
+                ShapeKind::new_box(&label)
+            };
+            let mut look = StyleAttr::simple();
+            if rounded {
+                look.rounded = 10;
+            }
+            let size = get_shape_size(orientation, &shape, FONT_SIZE, false);
+            graph.add_node(Element::create(shape, look, orientation, size))
+        })
+    };
+
+    for (groups, edges) in statements {
+        for group in &groups {
+            for node in group {
+                handle_of(&mut graph, &node.id);
+            }
+        }
+        for (i, edge) in edges.iter().enumerate() {
+            let mut look = StyleAttr::simple();
+            if edge.thick {
+                look.line_width = 4;
+            }
+            for from in &groups[i] {
+                for to in &groups[i + 1] {
+                    let arrow = Arrow::new(
+                        edge.start,
+                        edge.end,
+                        edge.style,
+                        &edge.label,
+                        &look,
+                        &None,
+                        &None,
+                    );
+                    let a = handle_of(&mut graph, &from.id);
+                    let b = handle_of(&mut graph, &to.id);
+                    graph.add_edge(arrow, a, b);
+                }
+            }
+        }
+    }
+
+    Ok(graph)
+}
@@ -0,0 +1,168 @@
+//! Notes that share a name across folders, and the links that are ambiguous
+//! because of it
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::history::Journal;
+use crate::links::{format_wiki_link, line_at, rewrite_wiki_links, LinkRewrite};
+use crate::{parse_wiki_links, FsResult, NoteIndex, WikiLink};
+
+/// A wiki link whose bare target matches several notes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbiguousLink {
+    pub source_path: String,
+    pub source_name: String,
+    /// Line number of the link (1-based)
+    pub line: usize,
+    pub link: WikiLink,
+    /// The note the link resolves to from its source note
+    pub resolved_path: String,
+}
+
+/// A note name used by more than one note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateName {
+    pub name: String,
+    /// Notes with this name, shortest path first
+    pub notes: Vec<String>,
+    /// Links that do not say which of the notes they mean
+    pub ambiguous_links: Vec<AmbiguousLink>,
+}
+
+/// Notes a wiki link could mean, if there is more than one
+fn ambiguous_candidates(index: &NoteIndex, link: &WikiLink) -> Option<Vec<usize>> {
+    let candidates = index.candidates(&link.target, None);
+    (!link.is_markdown && candidates.len() > 1).then_some(candidates)
+}
+
+/// List every note name shared by several notes, with the links that are
+/// ambiguous because of it - Tauri command
+#[tauri::command]
+pub fn get_duplicate_names(vault_path: String) -> FsResult<Vec<DuplicateName>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut duplicates: BTreeMap<String, DuplicateName> = BTreeMap::new();
+
+    for i in 0..index.notes.len() {
+        let name = index.name(i);
+        let notes = index.candidates(&name, None);
+        if notes.len() > 1 {
+            duplicates
+                .entry(name.to_lowercase())
+                .or_insert_with(|| DuplicateName {
+                    name,
+                    notes: notes
+                        .iter()
+                        .map(|n| index.notes[*n].to_string_lossy().to_string())
+                        .collect(),
+                    ambiguous_links: Vec::new(),
+                });
+        }
+    }
+
+    for (i, path) in index.notes.iter().enumerate() {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        for link in parse_wiki_links(&content) {
+            let candidates = match ambiguous_candidates(&index, &link) {
+                Some(c) => c,
+                None => continue,
+            };
+            let resolved = index.resolve_link(&link, path).unwrap_or(candidates[0]);
+            let key = index.name(resolved).to_lowercase();
+
+            if let Some(duplicate) = duplicates.get_mut(&key) {
+                duplicate.ambiguous_links.push(AmbiguousLink {
+                    source_path: path.to_string_lossy().to_string(),
+                    source_name: index.name(i),
+                    line: line_at(&content, link.start),
+                    resolved_path: index.notes[resolved].to_string_lossy().to_string(),
+                    link,
+                });
+            }
+        }
+    }
+
+    FsResult::ok(duplicates.into_values().collect())
+}
+
+/// Rewrite ambiguous links to the shortest path that identifies the note
+/// they currently resolve to - Tauri command
+///
+/// Only links to `name` are changed when it is given. Links without display
+/// text keep showing the name they were written with (`[[a/meeting|meeting]]`).
+#[tauri::command]
+pub fn qualify_ambiguous_links(vault_path: String, name: Option<String>) -> FsResult<LinkRewrite> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let name = name.map(|n| n.trim().to_lowercase());
+    let mut summary = LinkRewrite::default();
+    let mut journal = Journal::new(Some(vault.clone()), "Qualify ambiguous links");
+
+    for path in &index.notes {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        let (updated, count) = rewrite_wiki_links(&content, |link| {
+            ambiguous_candidates(&index, link)?;
+            let resolved = index.resolve_link(link, path)?;
+            if name
+                .as_ref()
+                .is_some_and(|n| *n != index.name(resolved).to_lowercase())
+            {
+                return None;
+            }
+
+            let target = index.link_target(resolved);
+            if target.eq_ignore_ascii_case(&link.target) {
+                return None;
+            }
+
+            let display = link
+                .display_text
+                .clone()
+                .unwrap_or_else(|| link.target.clone());
+            Some(format_wiki_link(
+                &target,
+                link.anchor().as_deref(),
+                Some(&display),
+            ))
+        });
+
+        if count == 0 {
+            continue;
+        }
+
+        if let Err(e) = journal.write(path, &content, &updated) {
+            return FsResult::err(&format!("Failed to update {}: {}", path.display(), e));
+        }
+        summary
+            .changed_notes
+            .push(path.to_string_lossy().to_string());
+        summary.links_updated += count;
+    }
+
+    match journal.finish() {
+        Ok(id) => summary.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Updated, but failed to save history: {}", e)),
+    }
+    FsResult::ok(summary)
+}
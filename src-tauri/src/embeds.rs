@@ -0,0 +1,239 @@
+//! Transclusion: expand `![[note]]`, `![[note#heading]]` and `![[note#^block]]`
+//! embeds recursively, keeping a source map back to the original notes
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::attachments::looks_like_attachment;
+use crate::blocks::{find_block, parse_blocks};
+use crate::headings::{find_heading, parse_headings, section_range};
+use crate::{frontmatter, parse_wiki_links, FsResult, NoteIndex, WikiLink};
+
+/// Embed depth used when `max_depth` is not given
+const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// A stretch of expanded content copied from one place in a source note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SourceSpan {
+    /// Start in the expanded content (byte offset)
+    pub start: usize,
+    /// End in the expanded content (byte offset)
+    pub end: usize,
+    pub source_path: String,
+    /// Start in the source note (byte offset)
+    pub source_start: usize,
+    /// End in the source note (byte offset)
+    pub source_end: usize,
+    /// Embed nesting level (0 for the note itself)
+    pub depth: usize,
+}
+
+/// Why an embed was left unexpanded
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedProblemKind {
+    /// The note, heading or block does not exist
+    Missing,
+    /// The embed would include itself
+    Cycle,
+    /// Nested deeper than `max_depth`
+    DepthLimit,
+}
+
+/// An embed that was left as written
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedProblem {
+    pub kind: EmbedProblemKind,
+    /// Note containing the embed
+    pub source_path: String,
+    /// The embed as written
+    pub link: WikiLink,
+    /// For cycles, the chain of notes from the outermost one back to the repeat
+    pub chain: Vec<String>,
+}
+
+/// A note with its embeds expanded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpandedNote {
+    pub content: String,
+    /// Where each part of `content` came from, in order
+    pub source_map: Vec<SourceSpan>,
+    pub problems: Vec<EmbedProblem>,
+}
+
+/// Recursive embed expansion state
+struct Expander<'a> {
+    index: &'a NoteIndex,
+    max_depth: usize,
+    contents: HashMap<usize, String>,
+    /// Notes being expanded, outermost first
+    stack: Vec<usize>,
+    /// Note and offset of each embed currently being expanded
+    active: Vec<(usize, usize)>,
+    output: String,
+    source_map: Vec<SourceSpan>,
+    problems: Vec<EmbedProblem>,
+}
+
+impl<'a> Expander<'a> {
+    fn content(&mut self, note: usize) -> String {
+        let index = self.index;
+        self.contents
+            .entry(note)
+            .or_insert_with(|| fs::read_to_string(&index.notes[note]).unwrap_or_default())
+            .clone()
+    }
+
+    /// The byte range of a note (without frontmatter) or of one of its
+    /// sections or blocks, trailing blank lines trimmed
+    fn embedded_range(&mut self, note: usize, link: &WikiLink) -> Option<(usize, usize)> {
+        let content = self.content(note);
+
+        let (start, end) = if let Some(heading) = &link.heading {
+            let headings = parse_headings(&content);
+            let found = find_heading(&headings, heading)?;
+            section_range(&content, &headings, found)
+        } else if let Some(block_id) = &link.block_id {
+            let block = find_block(&parse_blocks(&content), block_id)?.clone();
+            // Leave out an inline `^id` marker
+            let marker = format!("^{}", block.id);
+            if content[..block.end].ends_with(&marker) {
+                (block.start, block.end - marker.len())
+            } else {
+                (block.start, block.end)
+            }
+        } else {
+            let body_start = frontmatter::parse_frontmatter(&content).map_or(0, |fm| fm.body_start);
+            (body_start, content.len())
+        };
+
+        Some((start, start + content[start..end].trim_end().len()))
+    }
+
+    fn copy(&mut self, note: usize, content: &str, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        let output_start = self.output.len();
+        self.output.push_str(&content[start..end]);
+        self.source_map.push(SourceSpan {
+            start: output_start,
+            end: self.output.len(),
+            source_path: self.index.notes[note].to_string_lossy().to_string(),
+            source_start: start,
+            source_end: end,
+            depth: self.stack.len().saturating_sub(1),
+        });
+    }
+
+    fn problem(&mut self, kind: EmbedProblemKind, note: usize, link: WikiLink, chain: Vec<String>) {
+        self.problems.push(EmbedProblem {
+            kind,
+            source_path: self.index.notes[note].to_string_lossy().to_string(),
+            link,
+            chain,
+        });
+    }
+
+    /// Copy `start..end` of a note into the output, expanding its embeds
+    fn expand(&mut self, note: usize, start: usize, end: usize) {
+        let content = self.content(note);
+        self.stack.push(note);
+        let mut cursor = start;
+
+        let embeds = parse_wiki_links(&content).into_iter().filter(|l| {
+            l.is_embed && l.start >= start && l.end <= end && !looks_like_attachment(&l.target)
+        });
+
+        for link in embeds {
+            let target = self.index.resolve_link(&link, &self.index.notes[note]);
+            let range = target.and_then(|t| self.embedded_range(t, &link));
+            let (target, (embed_start, embed_end)) = match (target, range) {
+                (Some(t), Some(r)) => (t, r),
+                _ => {
+                    // Leave the embed as written
+                    self.problem(EmbedProblemKind::Missing, note, link, Vec::new());
+                    continue;
+                }
+            };
+
+            // A cycle: the embedded range contains an embed being expanded
+            let repeats = self
+                .active
+                .iter()
+                .chain(std::iter::once(&(note, link.start)))
+                .any(|(n, at)| *n == target && embed_start <= *at && *at < embed_end);
+            if repeats {
+                let mut chain: Vec<String> = self
+                    .stack
+                    .iter()
+                    .map(|n| self.index.relative_path(*n))
+                    .collect();
+                chain.push(self.index.relative_path(target));
+                self.problem(EmbedProblemKind::Cycle, note, link, chain);
+                continue;
+            }
+            if self.stack.len() > self.max_depth {
+                self.problem(EmbedProblemKind::DepthLimit, note, link, Vec::new());
+                continue;
+            }
+
+            self.copy(note, &content, cursor, link.start);
+            self.active.push((note, link.start));
+            self.expand(target, embed_start, embed_end);
+            self.active.pop();
+            cursor = link.end;
+        }
+
+        self.copy(note, &content, cursor, end);
+        self.stack.pop();
+    }
+}
+
+/// Expand a note's embeds recursively, up to `max_depth` levels - Tauri command
+///
+/// Embeds that are missing, would form a cycle or are nested too deeply are
+/// left as written and reported in `problems`. Attachment embeds (images,
+/// PDFs, ...) are always left as written.
+#[tauri::command]
+pub fn resolve_embeds(
+    vault_path: String,
+    path: String,
+    max_depth: Option<usize>,
+) -> FsResult<ExpandedNote> {
+    let vault = PathBuf::from(&vault_path);
+    let index = NoteIndex::build(&vault);
+
+    let note = match index.position(&PathBuf::from(&path)) {
+        Some(note) => note,
+        None => return FsResult::err("Note not found in vault"),
+    };
+
+    let mut expander = Expander {
+        index: &index,
+        max_depth: max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+        contents: HashMap::new(),
+        stack: Vec::new(),
+        active: Vec::new(),
+        output: String::new(),
+        source_map: Vec::new(),
+        problems: Vec::new(),
+    };
+
+    match fs::read_to_string(&index.notes[note]) {
+        Ok(content) => {
+            let end = content.len();
+            expander.contents.insert(note, content);
+            expander.expand(note, 0, end);
+        }
+        Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+    }
+
+    FsResult::ok(ExpandedNote {
+        content: expander.output,
+        source_map: expander.source_map,
+        problems: expander.problems,
+    })
+}
@@ -0,0 +1,275 @@
+//! Vault-wide find and replace, with a dry-run preview and undo
+
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::history::{relative_path, Journal};
+use crate::links::line_at;
+use crate::{collect_notes, note_tags, FsResult};
+
+/// Options for `find_replace`; every field is optional
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FindReplaceOptions {
+    /// Treat the pattern as a regular expression; the replacement can then
+    /// use `$1`, `${name}` and `$$` for a literal `$`
+    #[serde(default)]
+    pub regex: bool,
+    /// Match case exactly (default: case-insensitive)
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only match whole words
+    #[serde(default)]
+    pub whole_word: bool,
+    /// Give the replacement the case of each match: "FOO" -> "BAR",
+    /// "Foo" -> "Bar", "foo" -> "bar"
+    #[serde(default)]
+    pub preserve_case: bool,
+    /// Only notes under this folder (relative to the vault root)
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// Only notes with this tag (nested tags such as "work/x" match "work")
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Return the preview without changing any file
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// One replacement, shown on the lines it touches
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplacementPreview {
+    /// First line of the match (1-based)
+    pub line: usize,
+    /// Byte offsets of the match in the original note
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    pub replacement: String,
+    /// The affected lines before and after the replacement
+    pub before: String,
+    pub after: String,
+}
+
+/// The replacements in one note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileReplacements {
+    pub path: String,
+    /// Path relative to the vault (e.g., "folder/note.md")
+    pub relative_path: String,
+    pub replacements: Vec<ReplacementPreview>,
+}
+
+/// Result of `find_replace`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindReplaceResult {
+    pub files: Vec<FileReplacements>,
+    pub total_replacements: usize,
+    /// The recorded operation, for `undo_operation` (not set for a dry run
+    /// or when nothing changed)
+    pub operation_id: Option<String>,
+}
+
+/// Build the search regex from the pattern and options
+fn search_regex(pattern: &str, options: &FindReplaceOptions) -> Result<Regex, String> {
+    let pattern = if options.regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// Change the case of `replacement` to follow `matched`
+fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return replacement.to_string();
+    }
+
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if letters.iter().all(|c| c.is_lowercase()) {
+        replacement.to_lowercase()
+    } else if letters[0].is_uppercase() && letters[1..].iter().all(|c| c.is_lowercase()) {
+        let mut chars = replacement.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// The replacement text for one match
+fn replacement_for(caps: &Captures, replacement: &str, options: &FindReplaceOptions) -> String {
+    let text = if options.regex {
+        let mut expanded = String::new();
+        caps.expand(replacement, &mut expanded);
+        expanded
+    } else {
+        replacement.to_string()
+    };
+
+    if options.preserve_case {
+        match_case(&caps[0], &text)
+    } else {
+        text
+    }
+}
+
+/// Replace every match in `content`, returning the new content and a preview
+/// of each replacement
+fn replace_in(
+    content: &str,
+    re: &Regex,
+    replacement: &str,
+    options: &FindReplaceOptions,
+) -> (String, Vec<ReplacementPreview>) {
+    let mut updated = String::with_capacity(content.len());
+    let mut previews = Vec::new();
+    let mut cursor = 0;
+
+    for caps in re.captures_iter(content) {
+        let m = match caps.get(0) {
+            Some(m) if !m.is_empty() => m,
+            _ => continue,
+        };
+        let text = replacement_for(&caps, replacement, options);
+
+        let line_start = content[..m.start()].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[m.end()..]
+            .find('\n')
+            .map_or(content.len(), |i| m.end() + i);
+        let before = content[line_start..line_end].trim_end_matches('\r');
+        let after = format!(
+            "{}{}{}",
+            &content[line_start..m.start()],
+            text,
+            &content[m.end()..line_end]
+        );
+
+        previews.push(ReplacementPreview {
+            line: line_at(content, m.start()),
+            start: m.start(),
+            end: m.end(),
+            matched: m.as_str().to_string(),
+            replacement: text.clone(),
+            before: before.to_string(),
+            after: after.trim_end_matches('\r').to_string(),
+        });
+
+        updated.push_str(&content[cursor..m.start()]);
+        updated.push_str(&text);
+        cursor = m.end();
+    }
+
+    updated.push_str(&content[cursor..]);
+    (updated, previews)
+}
+
+/// Check the tag filter against a note's tags
+fn has_tag(content: &str, tag: &str) -> bool {
+    let tag = tag.trim_start_matches('#').to_lowercase();
+    note_tags(content).iter().any(|t| {
+        let t = t.to_lowercase();
+        t == tag || t.starts_with(&format!("{}/", tag))
+    })
+}
+
+/// Find and replace text across the vault - Tauri command
+///
+/// With `dry_run`, only the preview is returned. Otherwise every changed
+/// note is written and the whole batch is recorded as one operation, which
+/// `undo_operation` or `undo_last_operation` reverts.
+#[tauri::command]
+pub fn find_replace(
+    vault_path: String,
+    pattern: String,
+    replacement: String,
+    options: Option<FindReplaceOptions>,
+) -> FsResult<FindReplaceResult> {
+    let vault = PathBuf::from(&vault_path);
+    let options = options.unwrap_or_default();
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+    if pattern.is_empty() {
+        return FsResult::err("Search pattern is empty");
+    }
+    let re = match search_regex(&pattern, &options) {
+        Ok(re) => re,
+        Err(e) => return FsResult::err(&e),
+    };
+
+    let root = match options.folder.as_deref().map(|f| f.trim_matches('/')) {
+        Some(f) if !f.is_empty() => vault.join(f),
+        _ => vault.clone(),
+    };
+
+    let mut files = Vec::new();
+    let mut changes = Vec::new();
+    for path in collect_notes(&root) {
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        if options
+            .tag
+            .as_deref()
+            .is_some_and(|tag| !has_tag(&content, tag))
+        {
+            continue;
+        }
+
+        let (updated, replacements) = replace_in(&content, &re, &replacement, &options);
+        if replacements.is_empty() {
+            continue;
+        }
+        files.push(FileReplacements {
+            path: path.to_string_lossy().to_string(),
+            relative_path: relative_path(&vault, &path),
+            replacements,
+        });
+        if updated != content {
+            changes.push((path, content, updated));
+        }
+    }
+
+    let total_replacements = files.iter().map(|f| f.replacements.len()).sum();
+    let mut result = FindReplaceResult {
+        files,
+        total_replacements,
+        operation_id: None,
+    };
+    if options.dry_run || changes.is_empty() {
+        return FsResult::ok(result);
+    }
+
+    // What was written is recorded even after a failure, so it can be undone
+    let label = format!("Replace \"{}\" with \"{}\"", pattern, replacement);
+    let mut journal = Journal::new(Some(vault.clone()), label);
+    for (path, before, after) in changes {
+        if let Err(e) = journal.write(&path, &before, &after) {
+            return FsResult::err(&format!("Failed to update {}: {}", path.display(), e));
+        }
+    }
+    match journal.finish() {
+        Ok(id) => result.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Replaced, but failed to save history: {}", e)),
+    }
+    FsResult::ok(result)
+}
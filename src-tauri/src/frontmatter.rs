@@ -0,0 +1,202 @@
+//! Minimal YAML frontmatter support for the keys the backend cares about
+//!
+//! Only flat `key: value`, `key: [a, b]` and `key:` + `- item` lists are
+//! understood. Anything else is preserved untouched when rewriting.
+
+/// A frontmatter value: either a single scalar or a list of scalars
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontmatterValue {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+impl FrontmatterValue {
+    /// The value as a single string (first item for lists)
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FrontmatterValue::Scalar(s) => Some(s.as_str()),
+            FrontmatterValue::List(items) => items.first().map(|s| s.as_str()),
+        }
+    }
+
+    /// The value as a list (a scalar becomes a one-item list)
+    pub fn as_list(&self) -> Vec<String> {
+        match self {
+            FrontmatterValue::Scalar(s) if s.is_empty() => Vec::new(),
+            FrontmatterValue::Scalar(s) => vec![s.clone()],
+            FrontmatterValue::List(items) => items.clone(),
+        }
+    }
+}
+
+/// A top-level frontmatter entry and the lines it spans
+#[derive(Debug, Clone)]
+pub struct FrontmatterEntry {
+    pub key: String,
+    pub value: FrontmatterValue,
+    /// First line of the entry (index into `Frontmatter::lines`)
+    pub first_line: usize,
+    /// One past the last line of the entry
+    pub end_line: usize,
+}
+
+/// Parsed frontmatter block at the top of a note
+#[derive(Debug, Clone)]
+pub struct Frontmatter {
+    pub entries: Vec<FrontmatterEntry>,
+    /// Lines between the `---` fences
+    pub lines: Vec<String>,
+    /// Byte offset where the note body starts (after the closing fence)
+    pub body_start: usize,
+}
+
+impl Frontmatter {
+    pub fn get(&self, key: &str) -> Option<&FrontmatterValue> {
+        self.entries
+            .iter()
+            .find(|e| e.key.eq_ignore_ascii_case(key))
+            .map(|e| &e.value)
+    }
+}
+
+/// Strip matching single or double quotes around a scalar
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parse an inline value: `[a, "b"]` becomes a list, anything else a scalar
+fn parse_inline_value(value: &str) -> FrontmatterValue {
+    let value = value.trim();
+    if value.starts_with('[') && value.ends_with(']') {
+        let items = value[1..value.len() - 1]
+            .split(',')
+            .map(unquote)
+            .filter(|s| !s.is_empty())
+            .collect();
+        FrontmatterValue::List(items)
+    } else {
+        FrontmatterValue::Scalar(unquote(value))
+    }
+}
+
+/// Parse the frontmatter block at the start of `content`, if any
+pub fn parse_frontmatter(content: &str) -> Option<Frontmatter> {
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+
+    // Find the closing fence
+    let mut lines: Vec<String> = Vec::new();
+    let mut offset = first_line_end + 1;
+    let mut body_start = None;
+    while offset <= content.len() {
+        let line_end = content[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(content.len());
+        let line = content[offset..line_end].trim_end_matches('\r');
+
+        if line == "---" || line == "..." {
+            body_start = Some((line_end + 1).min(content.len()));
+            break;
+        }
+
+        lines.push(line.to_string());
+        if line_end == content.len() {
+            break;
+        }
+        offset = line_end + 1;
+    }
+    let body_start = body_start?;
+
+    let mut entries: Vec<FrontmatterEntry> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let is_top_level = !line.starts_with(' ') && !line.starts_with('\t');
+        let trimmed = line.trim();
+
+        if is_top_level
+            && !trimmed.is_empty()
+            && !trimmed.starts_with('#')
+            && !trimmed.starts_with('-')
+        {
+            if let Some((key, value)) = trimmed.split_once(':') {
+                entries.push(FrontmatterEntry {
+                    key: key.trim().to_string(),
+                    value: parse_inline_value(value),
+                    first_line: i,
+                    end_line: i + 1,
+                });
+                continue;
+            }
+        }
+
+        // Continuation lines (e.g., `  - item`) belong to the previous entry
+        if let Some(entry) = entries.last_mut() {
+            if trimmed.is_empty() {
+                continue;
+            }
+            entry.end_line = i + 1;
+            if let Some(item) = trimmed.strip_prefix('-') {
+                let item = unquote(item);
+                match &mut entry.value {
+                    FrontmatterValue::List(items) => items.push(item),
+                    FrontmatterValue::Scalar(s) if s.is_empty() => {
+                        entry.value = FrontmatterValue::List(vec![item]);
+                    }
+                    FrontmatterValue::Scalar(_) => {}
+                }
+            }
+        }
+    }
+
+    Some(Frontmatter {
+        entries,
+        lines,
+        body_start,
+    })
+}
+
+/// Render frontmatter lines and a body back into note content
+fn assemble(lines: &[String], body: &str) -> String {
+    if lines.iter().all(|l| l.trim().is_empty()) {
+        return body.to_string();
+    }
+    format!("---\n{}\n---\n{}", lines.join("\n"), body)
+}
+
+/// Remove the given top-level keys from a note's frontmatter, dropping the
+/// block entirely if nothing is left
+pub fn remove_frontmatter_keys(content: &str, keys: &[&str]) -> String {
+    let fm = match parse_frontmatter(content) {
+        Some(fm) => fm,
+        None => return content.to_string(),
+    };
+
+    let mut keep = vec![true; fm.lines.len()];
+    for entry in &fm.entries {
+        if keys.iter().any(|k| entry.key.eq_ignore_ascii_case(k)) {
+            for flag in keep.iter_mut().take(entry.end_line).skip(entry.first_line) {
+                *flag = false;
+            }
+        }
+    }
+
+    let lines: Vec<String> = fm
+        .lines
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(l, _)| l.clone())
+        .collect();
+
+    assemble(&lines, &content[fm.body_start..])
+}
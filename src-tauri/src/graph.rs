@@ -0,0 +1,628 @@
+//! Note connection graph built from wiki links across the vault
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::attachments::{
+    index_by_file_name, looks_like_attachment, parse_attachment_refs, resolve_attachment,
+};
+use crate::{
+    collect_vault_files, get_modified_time, note_tags, parse_note_links, FsResult, NoteIndex,
+    NoteMeta,
+};
+
+/// Options for `get_graph`; every field is optional
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GraphOptions {
+    /// Add a node per tag, linked from the notes that use it
+    #[serde(default)]
+    pub include_tags: bool,
+    /// Add a node per linked attachment
+    #[serde(default)]
+    pub include_attachments: bool,
+    /// Keep notes without any connections
+    #[serde(default = "default_true")]
+    pub include_orphans: bool,
+    /// Add "ghost" nodes for link targets that do not exist
+    #[serde(default)]
+    pub include_unresolved: bool,
+    /// Only include notes under these folders (relative to the vault root)
+    #[serde(default)]
+    pub folders: Vec<String>,
+    /// Leave out notes under these folders
+    #[serde(default)]
+    pub exclude_folders: Vec<String>,
+    /// Build a local graph around this note (path or link target)
+    #[serde(default)]
+    pub center: Option<String>,
+    /// How many links away from `center` to include
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+fn default_depth() -> usize {
+    1
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        GraphOptions {
+            include_tags: false,
+            include_attachments: false,
+            include_orphans: true,
+            include_unresolved: false,
+            folders: Vec::new(),
+            exclude_folders: Vec::new(),
+            center: None,
+            depth: default_depth(),
+        }
+    }
+}
+
+/// Kind of graph node
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphNodeKind {
+    Note,
+    Tag,
+    Attachment,
+    /// A link target that does not exist
+    Ghost,
+}
+
+/// A node in the graph
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GraphNode {
+    /// Stable id: relative path for notes and attachments, "tag:..." or "ghost:..." otherwise
+    pub id: String,
+    pub label: String,
+    pub kind: GraphNodeKind,
+    /// Absolute path for notes and attachments
+    pub path: Option<String>,
+    pub in_degree: usize,
+    pub out_degree: usize,
+}
+
+/// A directed edge between two nodes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// Number of links from source to target
+    pub count: usize,
+}
+
+/// Graph data for the graph view
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphData {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Check whether a relative path lies inside a folder (relative to the vault root)
+pub(crate) fn in_folder(relative: &str, folder: &str) -> bool {
+    let folder = folder.trim_matches('/');
+    folder.is_empty() || relative == folder || relative.starts_with(&format!("{}/", folder))
+}
+
+/// Nodes keyed by id and link counts keyed by (source, target)
+type Graph = (
+    BTreeMap<String, GraphNode>,
+    BTreeMap<(String, String), usize>,
+);
+
+/// Add a node unless one with the same id exists
+fn add_node(
+    nodes: &mut BTreeMap<String, GraphNode>,
+    id: &str,
+    label: &str,
+    kind: GraphNodeKind,
+    path: Option<&Path>,
+) {
+    nodes.entry(id.to_string()).or_insert_with(|| GraphNode {
+        id: id.to_string(),
+        label: label.to_string(),
+        kind,
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        in_degree: 0,
+        out_degree: 0,
+    });
+}
+
+/// Build the whole-vault graph (before any local-graph filtering)
+fn build_graph(vault: &PathBuf, index: &NoteIndex, options: &GraphOptions) -> Graph {
+    let included: Vec<bool> = (0..index.notes.len())
+        .map(|i| {
+            let relative = index.relative_path(i);
+            (options.folders.is_empty() || options.folders.iter().any(|f| in_folder(&relative, f)))
+                && !options
+                    .exclude_folders
+                    .iter()
+                    .any(|f| in_folder(&relative, f))
+        })
+        .collect();
+
+    let files_by_name = if options.include_attachments {
+        index_by_file_name(&collect_vault_files(vault))
+    } else {
+        HashMap::new()
+    };
+
+    let mut nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+    let mut edges: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+    for (i, path) in index.notes.iter().enumerate() {
+        if !included[i] {
+            continue;
+        }
+        let source = index.relative_path(i);
+        add_node(
+            &mut nodes,
+            &source,
+            &index.name(i),
+            GraphNodeKind::Note,
+            Some(path),
+        );
+
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        for link in parse_note_links(&content) {
+            let target_id = match index.resolve_link(&link, path) {
+                Some(j) if included[j] && j != i => {
+                    let id = index.relative_path(j);
+                    add_node(
+                        &mut nodes,
+                        &id,
+                        &index.name(j),
+                        GraphNodeKind::Note,
+                        Some(&index.notes[j]),
+                    );
+                    id
+                }
+                Some(_) => continue,
+                // Attachments are handled below
+                None if looks_like_attachment(&link.target) => continue,
+                None if options.include_unresolved => {
+                    let id = format!("ghost:{}", link.target.to_lowercase());
+                    add_node(&mut nodes, &id, &link.target, GraphNodeKind::Ghost, None);
+                    id
+                }
+                None => continue,
+            };
+            *edges.entry((source.clone(), target_id)).or_insert(0) += 1;
+        }
+
+        if options.include_tags {
+            for tag in note_tags(&content) {
+                let id = format!("tag:{}", tag.to_lowercase());
+                add_node(
+                    &mut nodes,
+                    &id,
+                    &format!("#{}", tag),
+                    GraphNodeKind::Tag,
+                    None,
+                );
+                *edges.entry((source.clone(), id)).or_insert(0) += 1;
+            }
+        }
+
+        if options.include_attachments {
+            let note_dir = path.parent().unwrap_or(vault);
+            for r in parse_attachment_refs(&content) {
+                if index.resolve(&r.target).is_some() {
+                    continue;
+                }
+                if let Some(file) = resolve_attachment(vault, note_dir, &r.target, &files_by_name) {
+                    let id = file
+                        .strip_prefix(vault)
+                        .unwrap_or(&file)
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    let label = file
+                        .file_name()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    add_node(
+                        &mut nodes,
+                        &id,
+                        &label,
+                        GraphNodeKind::Attachment,
+                        Some(&file),
+                    );
+                    *edges.entry((source.clone(), id)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    (nodes, edges)
+}
+
+/// Find the node id of the local graph center from a path or link target
+fn center_id(
+    index: &NoteIndex,
+    center: &str,
+    nodes: &BTreeMap<String, GraphNode>,
+) -> Option<String> {
+    let path = Path::new(center);
+    if let Ok(relative) = path.strip_prefix(&index.vault) {
+        let id = relative.to_string_lossy().replace('\\', "/");
+        if nodes.contains_key(&id) {
+            return Some(id);
+        }
+    }
+    if nodes.contains_key(center) {
+        return Some(center.to_string());
+    }
+
+    index
+        .resolve(center)
+        .map(|i| index.relative_path(i))
+        .filter(|id| nodes.contains_key(id))
+}
+
+/// Get graph nodes and edges for the vault - Tauri command
+#[tauri::command]
+pub fn get_graph(vault_path: String, options: Option<GraphOptions>) -> FsResult<GraphData> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let options = options.unwrap_or_default();
+    let index = NoteIndex::build(&vault);
+    let (mut nodes, mut edges) = build_graph(&vault, &index, &options);
+
+    // Local graph: breadth-first over links in either direction
+    let mut center = None;
+    if let Some(c) = &options.center {
+        let id = match center_id(&index, c, &nodes) {
+            Some(id) => id,
+            None => return FsResult::err("Center note not found"),
+        };
+
+        let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (source, target) in edges.keys() {
+            neighbours.entry(source).or_default().push(target);
+            neighbours.entry(target).or_default().push(source);
+        }
+
+        let mut keep: HashSet<String> = HashSet::new();
+        let mut queue: VecDeque<(&str, usize)> = VecDeque::new();
+        keep.insert(id.clone());
+        queue.push_back((&id, 0));
+        while let Some((node, dist)) = queue.pop_front() {
+            if dist >= options.depth {
+                continue;
+            }
+            for next in neighbours.get(node).into_iter().flatten() {
+                if keep.insert(next.to_string()) {
+                    queue.push_back((next, dist + 1));
+                }
+            }
+        }
+
+        nodes.retain(|id, _| keep.contains(id));
+        edges.retain(|(s, t), _| keep.contains(s) && keep.contains(t));
+        center = Some(id);
+    }
+
+    for (source, target) in edges.keys() {
+        if let Some(node) = nodes.get_mut(source) {
+            node.out_degree += 1;
+        }
+        if let Some(node) = nodes.get_mut(target) {
+            node.in_degree += 1;
+        }
+    }
+
+    if !options.include_orphans {
+        nodes
+            .retain(|id, node| node.in_degree + node.out_degree > 0 || Some(id) == center.as_ref());
+    }
+
+    FsResult::ok(GraphData {
+        nodes: nodes.into_values().collect(),
+        edges: edges
+            .into_iter()
+            .map(|((source, target), count)| GraphEdge {
+                source,
+                target,
+                count,
+            })
+            .collect(),
+    })
+}
+
+/// Resolved wiki links between notes: `links[i]` maps target note -> link count
+struct NoteLinks {
+    index: NoteIndex,
+    links: Vec<BTreeMap<usize, usize>>,
+}
+
+impl NoteLinks {
+    /// Parse every note once and resolve its links to other notes
+    fn collect(vault: &PathBuf) -> NoteLinks {
+        let index = NoteIndex::build(vault);
+        let mut links = vec![BTreeMap::new(); index.notes.len()];
+
+        for (i, path) in index.notes.iter().enumerate() {
+            if let Ok(content) = fs::read_to_string(path) {
+                for link in parse_note_links(&content) {
+                    if let Some(j) = index.resolve_link(&link, path) {
+                        if j != i {
+                            *links[i].entry(j).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        NoteLinks { index, links }
+    }
+
+    fn in_degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![0; self.links.len()];
+        for targets in &self.links {
+            for target in targets.keys() {
+                degrees[*target] += 1;
+            }
+        }
+        degrees
+    }
+
+    fn meta(&self, i: usize) -> NoteMeta {
+        let path = &self.index.notes[i];
+        NoteMeta {
+            name: self.index.name(i),
+            path: path.to_string_lossy().to_string(),
+            modified: get_modified_time(path),
+        }
+    }
+
+    /// Undirected neighbours with link counts in both directions summed
+    fn undirected(&self) -> Vec<BTreeMap<usize, usize>> {
+        let mut neighbours = vec![BTreeMap::new(); self.links.len()];
+        for (i, targets) in self.links.iter().enumerate() {
+            for (j, count) in targets {
+                *neighbours[i].entry(*j).or_insert(0) += count;
+                *neighbours[*j].entry(i).or_insert(0) += count;
+            }
+        }
+        neighbours
+    }
+
+    /// PageRank over the link graph (damping 0.85); dangling notes spread
+    /// their rank evenly
+    fn pagerank(&self) -> Vec<f64> {
+        let n = self.links.len();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        const DAMPING: f64 = 0.85;
+        let mut rank = vec![1.0 / n as f64; n];
+
+        for _ in 0..100 {
+            let dangling: f64 = (0..n)
+                .filter(|i| self.links[*i].is_empty())
+                .map(|i| rank[i])
+                .sum();
+            let base = (1.0 - DAMPING) / n as f64 + DAMPING * dangling / n as f64;
+            let mut next = vec![base; n];
+
+            for (i, targets) in self.links.iter().enumerate() {
+                let total: usize = targets.values().sum();
+                for (j, count) in targets {
+                    next[*j] += DAMPING * rank[i] * (*count as f64) / (total as f64);
+                }
+            }
+
+            let delta: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if delta < 1e-9 {
+                break;
+            }
+        }
+        rank
+    }
+
+    /// Community clusters by label propagation over undirected, weighted links
+    fn clusters(&self) -> Vec<Vec<usize>> {
+        let neighbours = self.undirected();
+        let mut labels: Vec<usize> = (0..neighbours.len()).collect();
+
+        // Deterministic: fixed visiting order, ties go to the smallest label
+        for _ in 0..50 {
+            let mut changed = false;
+            for i in 0..neighbours.len() {
+                let mut weights: BTreeMap<usize, usize> = BTreeMap::new();
+                for (j, count) in &neighbours[i] {
+                    *weights.entry(labels[*j]).or_insert(0) += count;
+                }
+                let best = weights
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(label, _)| *label);
+                if let Some(best) = best {
+                    if best != labels[i] {
+                        labels[i] = best;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, label) in labels.iter().enumerate() {
+            groups.entry(*label).or_default().push(i);
+        }
+        let mut clusters: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+        clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        clusters
+    }
+}
+
+/// A note ranked by its connections
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HubInfo {
+    pub note: NoteMeta,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub pagerank: f64,
+}
+
+/// A group of densely linked notes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteCluster {
+    pub id: usize,
+    pub notes: Vec<NoteMeta>,
+}
+
+/// Link-graph analytics for curating the vault
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphAnalytics {
+    /// Notes with no inbound and no outbound links
+    pub orphans: Vec<NoteMeta>,
+    /// Notes that are linked to but link nowhere themselves
+    pub dead_ends: Vec<NoteMeta>,
+    /// Most linked-to notes
+    pub hubs_by_in_degree: Vec<HubInfo>,
+    /// Most central notes by PageRank
+    pub hubs_by_pagerank: Vec<HubInfo>,
+    /// Community clusters, largest first (single notes are left out)
+    pub clusters: Vec<NoteCluster>,
+}
+
+/// Compute orphans, dead ends, hubs and clusters - Tauri command
+#[tauri::command]
+pub fn get_graph_analytics(vault_path: String, top_n: Option<usize>) -> FsResult<GraphAnalytics> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let graph = NoteLinks::collect(&vault);
+    let top_n = top_n.unwrap_or(10);
+    let in_degrees = graph.in_degrees();
+    let pagerank = graph.pagerank();
+
+    let hub = |i: usize| HubInfo {
+        note: graph.meta(i),
+        in_degree: in_degrees[i],
+        out_degree: graph.links[i].len(),
+        pagerank: pagerank[i],
+    };
+
+    let mut by_in_degree: Vec<usize> = (0..in_degrees.len())
+        .filter(|i| in_degrees[*i] > 0)
+        .collect();
+    by_in_degree.sort_by(|a, b| in_degrees[*b].cmp(&in_degrees[*a]).then_with(|| a.cmp(b)));
+
+    let mut by_pagerank: Vec<usize> = (0..pagerank.len()).collect();
+    by_pagerank.sort_by(|a, b| pagerank[*b].total_cmp(&pagerank[*a]).then_with(|| a.cmp(b)));
+
+    FsResult::ok(GraphAnalytics {
+        orphans: (0..in_degrees.len())
+            .filter(|i| in_degrees[*i] == 0 && graph.links[*i].is_empty())
+            .map(|i| graph.meta(i))
+            .collect(),
+        dead_ends: (0..in_degrees.len())
+            .filter(|i| in_degrees[*i] > 0 && graph.links[*i].is_empty())
+            .map(|i| graph.meta(i))
+            .collect(),
+        hubs_by_in_degree: by_in_degree.into_iter().take(top_n).map(hub).collect(),
+        hubs_by_pagerank: by_pagerank.into_iter().take(top_n).map(hub).collect(),
+        clusters: graph
+            .clusters()
+            .into_iter()
+            .enumerate()
+            .map(|(id, members)| NoteCluster {
+                id,
+                notes: members.into_iter().map(|i| graph.meta(i)).collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Find the shortest chain of links between two notes - Tauri command
+///
+/// `from` and `to` may be note paths or link targets. By default links are
+/// followed in their direction; pass `directed: false` to ignore direction.
+#[tauri::command]
+pub fn find_shortest_path(
+    vault_path: String,
+    from: String,
+    to: String,
+    directed: Option<bool>,
+) -> FsResult<Option<Vec<NoteMeta>>> {
+    let vault = PathBuf::from(&vault_path);
+    let graph = NoteLinks::collect(&vault);
+
+    let locate = |note: &str| {
+        graph
+            .index
+            .position(Path::new(note))
+            .or_else(|| graph.index.resolve(note))
+    };
+    let (start, goal) = match (locate(&from), locate(&to)) {
+        (Some(s), Some(g)) => (s, g),
+        _ => return FsResult::err("Note not found"),
+    };
+
+    let neighbours: Vec<Vec<usize>> = if directed.unwrap_or(true) {
+        graph
+            .links
+            .iter()
+            .map(|t| t.keys().copied().collect())
+            .collect()
+    } else {
+        graph
+            .undirected()
+            .iter()
+            .map(|t| t.keys().copied().collect())
+            .collect()
+    };
+
+    // Breadth-first search, remembering how each note was reached
+    let mut previous: Vec<Option<usize>> = vec![None; neighbours.len()];
+    let mut visited = vec![false; neighbours.len()];
+    let mut queue = VecDeque::new();
+    visited[start] = true;
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        if node == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while let Some(prev) = previous[current] {
+                path.push(prev);
+                current = prev;
+            }
+            path.reverse();
+            return FsResult::ok(Some(path.into_iter().map(|i| graph.meta(i)).collect()));
+        }
+        for next in &neighbours[node] {
+            if !visited[*next] {
+                visited[*next] = true;
+                previous[*next] = Some(node);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    FsResult::ok(None)
+}
@@ -0,0 +1,530 @@
+//! Markdown headings and the slugs used to match `[[note#heading]]` links
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::blocks::{find_block, parse_blocks, Block};
+use crate::history::Journal;
+use crate::links::{format_wiki_link, rewrite_wiki_links, LinkRewrite};
+use crate::{fenced_line_mask, frontmatter, split_link_anchor, FsResult, NoteIndex};
+
+/// A heading in note content
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Heading {
+    /// Heading level (1-6)
+    pub level: usize,
+    /// Heading text without the `#` markers
+    pub text: String,
+    /// Unique slug within the note; repeated headings get `-1`, `-2`, ...
+    pub slug: String,
+    /// Line number (1-based); the first text line of a setext heading
+    pub line: usize,
+    /// Byte offset of the start of the heading line
+    pub start: usize,
+    /// Byte offset of the end of the heading line (before the line break);
+    /// for a setext heading, the end of its underline
+    pub end: usize,
+    /// Written as text underlined with `===` or `---` rather than with `#`s
+    #[serde(default)]
+    pub setext: bool,
+}
+
+/// Where a wiki link points: the note and, for `[[note#heading]]` or
+/// `[[note#^block]]`, the heading or block
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkLocation {
+    pub path: String,
+    /// The matched heading, if the link has one and it exists
+    pub heading: Option<Heading>,
+    /// The matched block, if the link has one and it exists
+    pub block: Option<Block>,
+    /// The link names a heading or block that the note does not have
+    pub anchor_missing: bool,
+    /// Several headings match equally; `heading` is the first of them
+    pub ambiguous: bool,
+}
+
+/// Parse ATX (`## Title`) and setext (`Title` over `===` or `---`)
+/// headings, skipping frontmatter and fenced code
+pub(crate) fn parse_headings(content: &str) -> Vec<Heading> {
+    let body_start = frontmatter::parse_frontmatter(content).map_or(0, |fm| fm.body_start);
+    let in_code = fenced_line_mask(content);
+
+    let mut headings = Vec::new();
+    let mut slug_counts: HashMap<String, usize> = HashMap::new();
+    let mut push = |level: usize, text: String, line: usize, start: usize, end: usize, setext| {
+        let base = heading_slug(&text);
+        let count = slug_counts.entry(base.clone()).or_insert(0);
+        let slug = match *count {
+            0 => base,
+            n => format!("{}-{}", base, n),
+        };
+        *count += 1;
+
+        headings.push(Heading {
+            level,
+            text,
+            slug,
+            line,
+            start,
+            end,
+            setext,
+        });
+    };
+
+    // Paragraph lines seen so far, as (line index, start offset, text); a
+    // setext underline turns them into a heading
+    let mut paragraph: Vec<(usize, usize, &str)> = Vec::new();
+    // Inside a list item or quote, where text lines continue that block
+    let mut in_container = false;
+    let mut offset = 0;
+
+    for (i, line) in content.split('\n').enumerate() {
+        let start = offset;
+        offset += line.len() + 1;
+
+        let line = line.trim_end_matches('\r');
+        if start < body_start || in_code.get(i).copied().unwrap_or(false) || line.trim().is_empty()
+        {
+            paragraph.clear();
+            in_container = false;
+            continue;
+        }
+
+        if let Some((level, text)) = parse_atx_heading(line) {
+            paragraph.clear();
+            in_container = false;
+            push(level, text, i + 1, start, start + line.len(), false);
+            continue;
+        }
+
+        if let (Some(level), Some((first, first_start, _))) =
+            (setext_underline(line), paragraph.first())
+        {
+            let text = paragraph
+                .iter()
+                .map(|(_, _, text)| text.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+            push(
+                level,
+                text,
+                first + 1,
+                *first_start,
+                start + line.len(),
+                true,
+            );
+            paragraph.clear();
+            continue;
+        }
+
+        if starts_container(line) {
+            in_container = true;
+        }
+        if !in_container && can_be_setext_text(line, !paragraph.is_empty()) {
+            paragraph.push((i, start, line));
+        } else {
+            paragraph.clear();
+        }
+    }
+    headings
+}
+
+/// Whether a line starts a list item or block quote
+fn starts_container(line: &str) -> bool {
+    let text = line.trim_start();
+    let marker_end = match text.chars().next() {
+        Some('>') => return true,
+        Some('-' | '+' | '*') => 1,
+        _ => {
+            let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+            if !(1..=9).contains(&digits) || !text[digits..].starts_with(['.', ')']) {
+                return false;
+            }
+            digits + 1
+        }
+    };
+    text[marker_end..].is_empty() || text[marker_end..].starts_with([' ', '\t'])
+}
+
+/// The level a setext underline (`===` or `---`) gives the text above it
+fn setext_underline(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let marker = line.trim();
+    if indent > 3 || marker.is_empty() {
+        return None;
+    }
+    if marker.chars().all(|c| c == '=') {
+        Some(1)
+    } else if marker.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Whether a line can be (part of) the text of a setext heading: a plain
+/// paragraph line rather than indented code, a table row, HTML or a rule
+fn can_be_setext_text(line: &str, continues_paragraph: bool) -> bool {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let text = line.trim();
+    if indent > 3 && !continues_paragraph {
+        return false;
+    }
+    !(text.starts_with(['|', '<']) || setext_underline(line).is_some())
+}
+
+/// Parse a single ATX heading line into its level and text
+fn parse_atx_heading(line: &str) -> Option<(usize, String)> {
+    // Up to three spaces of indentation are allowed
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &rest[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // Drop an optional closing sequence of `#`s
+    let mut text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        text = without_closing.trim_end();
+    }
+
+    Some((level, text.to_string()))
+}
+
+/// Slug used to compare headings: lowercase, punctuation dropped, whitespace
+/// and dashes collapsed to single `-` (e.g., "1.2 What's New?" -> "12-whats-new")
+pub(crate) fn heading_slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut pending_dash = false;
+
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' {
+            pending_dash = true;
+        }
+    }
+    slug
+}
+
+/// Headings a link's `#heading` part may refer to, in document order.
+///
+/// Slugs are compared, so case and punctuation don't matter. A unique slug
+/// (`#install-1`) picks that exact heading; otherwise every heading with the
+/// same text matches. Nested references (`#Parent#Child`) only match a
+/// `Child` under `Parent`.
+pub(crate) fn heading_candidates<'a>(headings: &'a [Heading], reference: &str) -> Vec<&'a Heading> {
+    let parts: Vec<String> = reference
+        .split('#')
+        .map(heading_slug)
+        .filter(|s| !s.is_empty())
+        .collect();
+    let (last, parents) = match parts.split_last() {
+        Some(split) => split,
+        None => return Vec::new(),
+    };
+
+    let under_parents = |i: usize| {
+        // Walk up through the enclosing headings, matching parents innermost first
+        let mut wanted = parents.iter().rev().peekable();
+        let mut level = headings[i].level;
+        for ancestor in headings[..i].iter().rev() {
+            if ancestor.level < level {
+                level = ancestor.level;
+                if wanted.peek() == Some(&&heading_slug(&ancestor.text)) {
+                    wanted.next();
+                }
+            }
+        }
+        wanted.peek().is_none()
+    };
+
+    let exact: Vec<&Heading> = (0..headings.len())
+        .filter(|i| headings[*i].slug == *last && heading_slug(&headings[*i].text) != *last)
+        .filter(|i| under_parents(*i))
+        .map(|i| &headings[i])
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    (0..headings.len())
+        .filter(|i| heading_slug(&headings[*i].text) == *last && under_parents(*i))
+        .map(|i| &headings[i])
+        .collect()
+}
+
+/// Find the heading a link's `#heading` part refers to (the first match)
+pub(crate) fn find_heading<'a>(headings: &'a [Heading], reference: &str) -> Option<&'a Heading> {
+    heading_candidates(headings, reference).into_iter().next()
+}
+
+/// Texts of the headings enclosing `offset`, outermost first
+pub(crate) fn heading_path(headings: &[Heading], offset: usize) -> Vec<String> {
+    let mut path: Vec<&Heading> = Vec::new();
+    for heading in headings.iter().take_while(|h| h.start <= offset) {
+        while path.last().is_some_and(|h| h.level >= heading.level) {
+            path.pop();
+        }
+        path.push(heading);
+    }
+    path.into_iter().map(|h| h.text.clone()).collect()
+}
+
+/// Byte range of a heading's section: from the heading line up to the next
+/// heading of the same or a higher level (or the end of the content)
+pub(crate) fn section_range(
+    content: &str,
+    headings: &[Heading],
+    heading: &Heading,
+) -> (usize, usize) {
+    let end = headings
+        .iter()
+        .find(|h| h.start > heading.start && h.level <= heading.level)
+        .map_or(content.len(), |h| h.start);
+    (heading.start, end)
+}
+
+/// Resolve a wiki link to its note and heading or block position - Tauri command
+///
+/// `target` may include the anchor (`note#heading`, `note#^block`) or it can
+/// be passed separately. Returns `None` when the note does not exist.
+#[tauri::command]
+pub fn resolve_link_location(
+    vault_path: String,
+    target: String,
+    anchor: Option<String>,
+) -> FsResult<Option<LinkLocation>> {
+    let vault = PathBuf::from(&vault_path);
+    let (target, anchor) = match anchor {
+        Some(anchor) => (target, Some(anchor)),
+        None => {
+            let (note, anchor) = split_link_anchor(&target);
+            (note.to_string(), anchor.map(|a| a.to_string()))
+        }
+    };
+
+    let index = NoteIndex::build(&vault);
+    let note = match index.resolve(&target) {
+        Some(note) => note,
+        None => return FsResult::ok(None),
+    };
+    let path = &index.notes[note];
+
+    let mut location = LinkLocation {
+        path: path.to_string_lossy().to_string(),
+        heading: None,
+        block: None,
+        anchor_missing: false,
+        ambiguous: false,
+    };
+
+    let anchor = match anchor.filter(|a| !a.trim().is_empty()) {
+        Some(anchor) => anchor,
+        None => return FsResult::ok(Some(location)),
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+    };
+
+    if anchor.trim_start().starts_with('^') {
+        location.block = find_block(&parse_blocks(&content), &anchor).cloned();
+        location.anchor_missing = location.block.is_none();
+    } else {
+        let headings = parse_headings(&content);
+        let candidates = heading_candidates(&headings, &anchor);
+        location.anchor_missing = candidates.is_empty();
+        location.ambiguous = candidates.len() > 1;
+        location.heading = candidates.first().map(|h| (*h).clone());
+    }
+
+    FsResult::ok(Some(location))
+}
+
+/// Result of renaming a heading
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeadingRename {
+    pub heading: Heading,
+    /// Links updated to the new heading text (empty unless requested)
+    pub links: LinkRewrite,
+}
+
+/// Rename the heading on `line` of a note and, when `update_links` is set,
+/// rewrite every `[[note#old heading]]` in the vault to match - Tauri command
+///
+/// Links to other headings with the same text are rewritten too when the
+/// rename changes which `notes`, `notes-1`, ... slug they have.
+#[tauri::command]
+pub fn rename_heading(
+    vault_path: String,
+    path: String,
+    line: usize,
+    new_text: String,
+    update_links: Option<bool>,
+) -> FsResult<HeadingRename> {
+    let vault = PathBuf::from(&vault_path);
+    let note_path = PathBuf::from(&path);
+    let new_text = new_text.trim();
+
+    if new_text.is_empty() || new_text.contains(['\n', '\r']) {
+        return FsResult::err("Heading text must be a single non-empty line");
+    }
+
+    let content = match fs::read_to_string(&note_path) {
+        Ok(c) => c,
+        Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+    };
+    let headings = parse_headings(&content);
+    let old = match headings.iter().position(|h| h.line == line) {
+        Some(i) => i,
+        None => return FsResult::err("No heading on that line"),
+    };
+
+    let level = headings[old].level;
+    let renamed = if headings[old].setext {
+        // Keep the underline, replacing only the text above it
+        let underline = content[..headings[old].end].rfind('\n').unwrap_or(0);
+        let text_end = match content[..underline].ends_with('\r') {
+            true => underline - 1,
+            false => underline,
+        };
+        format!(
+            "{}{}{}",
+            &content[..headings[old].start],
+            new_text,
+            &content[text_end..]
+        )
+    } else {
+        format!(
+            "{}{} {}{}",
+            &content[..headings[old].start],
+            "#".repeat(level),
+            new_text,
+            &content[headings[old].end..]
+        )
+    };
+
+    let index = NoteIndex::build(&vault);
+    let note = index.position(&note_path);
+    let mut links = LinkRewrite::default();
+    let label = format!(
+        "Rename heading \"{}\" to \"{}\"",
+        headings[old].text, new_text
+    );
+    let mut journal = Journal::new(Some(vault.clone()), label);
+
+    // Duplicate headings are told apart by slug (`notes`, `notes-1`), so the
+    // rename can move other headings' slugs too
+    let new_headings = parse_headings(&renamed);
+
+    // Rewrites references to the renamed heading, and to headings whose slug
+    // it changed, in one note's content
+    let retarget = |text: &str, source: &Path| {
+        rewrite_wiki_links(text, |link| {
+            let heading = link.heading.as_deref()?;
+            if note.is_none() || index.resolve_link(link, source) != note {
+                return None;
+            }
+            let matched = find_heading(&headings, heading)?;
+            let moved = new_headings.iter().find(|h| h.line == matched.line)?;
+
+            // Keep any parent path, replacing only the last part
+            let with_parents = |part: &str| match heading.rsplit_once('#') {
+                Some((parents, _)) => format!("{}#{}", parents, part),
+                None => part.to_string(),
+            };
+            let points_at = |reference: &str| {
+                find_heading(&new_headings, reference).is_some_and(|h| h.line == matched.line)
+            };
+
+            if matched.line != line {
+                if points_at(heading) {
+                    return None;
+                }
+                let new_heading = with_parents(&moved.slug);
+                return Some(format_wiki_link(
+                    &link.target,
+                    Some(&new_heading),
+                    link.display_text.as_deref(),
+                ));
+            }
+
+            // The new text, unless an earlier heading with the same text takes it
+            let new_heading = match with_parents(new_text) {
+                by_text if points_at(&by_text) => by_text,
+                _ => with_parents(&moved.slug),
+            };
+            // Display text that repeats the heading (as in a TOC) follows it
+            let display = match link.display_text.as_deref() {
+                Some(d) if d == headings[old].text => Some(new_text),
+                other => other,
+            };
+            Some(format_wiki_link(&link.target, Some(&new_heading), display))
+        })
+    };
+
+    let update_links = update_links.unwrap_or(false) && note.is_some();
+    let (renamed, own_links) = if update_links {
+        retarget(&renamed, &note_path)
+    } else {
+        (renamed, 0)
+    };
+
+    if let Err(e) = journal.write(&note_path, &content, &renamed) {
+        return FsResult::err(&format!("Failed to write note: {}", e));
+    }
+    if own_links > 0 {
+        links.changed_notes.push(path.clone());
+        links.links_updated += own_links;
+    }
+
+    if update_links {
+        for other in index.notes.iter().filter(|p| **p != note_path) {
+            let text = match fs::read_to_string(other) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            let (updated, count) = retarget(&text, other);
+            if count == 0 {
+                continue;
+            }
+            if let Err(e) = journal.write(other, &text, &updated) {
+                return FsResult::err(&format!("Failed to update {}: {}", other.display(), e));
+            }
+            links
+                .changed_notes
+                .push(other.to_string_lossy().to_string());
+            links.links_updated += count;
+        }
+    }
+
+    let heading = parse_headings(&renamed)
+        .into_iter()
+        .find(|h| h.line == line)
+        .unwrap_or_else(|| headings[old].clone());
+
+    match journal.finish() {
+        Ok(id) => links.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Renamed, but failed to save history: {}", e)),
+    }
+    FsResult::ok(HeadingRename { heading, links })
+}
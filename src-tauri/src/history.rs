@@ -0,0 +1,601 @@
+//! Operation journal: undo and redo for commands that change vault files,
+//! kept in `.open-note/history.json`
+//!
+//! An operation records the content of every file it touched before and
+//! after, plus the folders it created, so undoing it restores exactly those
+//! files and redoing it applies the change again. The contents are stored in
+//! one file per operation under `.open-note/history/`, so recording a save
+//! only rewrites that operation and the small history index.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::note_ids::{self, APP_DIR};
+use crate::{write_file_atomic, FsResult};
+
+/// History file inside `APP_DIR`
+const HISTORY_FILE: &str = "history.json";
+
+/// Folder inside `APP_DIR` with the file contents of each operation
+const CONTENTS_DIR: &str = "history";
+
+/// Oldest operations are dropped beyond this many
+const MAX_OPERATIONS: usize = 50;
+
+/// Saves of the same note this close together (in seconds) are one operation
+const EDIT_MERGE_SECONDS: i64 = 300;
+
+/// What kind of command an operation came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// A note saved (or created) with `write_note`
+    WriteNote,
+    DeleteNote,
+    RenameNote,
+    CreateFolder,
+    /// A command that edits one or more notes or moves files, such as find
+    /// and replace, a link rewrite or trashing unused attachments
+    #[default]
+    EditNotes,
+}
+
+/// A file touched by an operation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct FileChange {
+    /// Path relative to the vault, with `/` separators
+    pub path: String,
+    /// Content before the operation; `None` if it created the file
+    #[serde(skip)]
+    pub before: Option<String>,
+    /// Content after the operation; `None` if it deleted the file
+    #[serde(skip)]
+    pub after: Option<String>,
+}
+
+/// A recorded operation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct Operation {
+    pub id: String,
+    #[serde(default)]
+    pub kind: OperationKind,
+    /// What the operation did (e.g., "Replace \"foo\" with \"bar\"")
+    pub label: String,
+    /// Unix time in seconds
+    pub timestamp: i64,
+    pub files: Vec<FileChange>,
+    /// Folders the operation created (relative, outermost first)
+    #[serde(default)]
+    pub folders: Vec<String>,
+    /// Notes moved as `(from, to)`, so undo and redo keep their IDs
+    #[serde(default)]
+    pub moves: Vec<(String, String)>,
+    /// Files moved as `(from, to)` without keeping their content, such as
+    /// attachments sent to the trash
+    #[serde(default)]
+    pub moved_files: Vec<(String, String)>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+impl Operation {
+    pub(crate) fn new(kind: OperationKind, label: impl Into<String>) -> Operation {
+        Operation {
+            id: String::new(),
+            kind,
+            label: label.into(),
+            timestamp: 0,
+            files: Vec::new(),
+            folders: Vec::new(),
+            moves: Vec::new(),
+            moved_files: Vec::new(),
+            undone: false,
+        }
+    }
+
+    /// Add a file change; `path` is absolute
+    pub(crate) fn change(
+        &mut self,
+        vault: &Path,
+        path: &Path,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        self.files.push(FileChange {
+            path: relative_path(vault, path),
+            before,
+            after,
+        });
+    }
+
+    /// Write the file contents to the operation's contents file
+    fn save_contents(&self, vault: &Path) -> Result<(), String> {
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        let path = contents_path(vault, &self.id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let contents: Vec<(&Option<String>, &Option<String>)> =
+            self.files.iter().map(|f| (&f.before, &f.after)).collect();
+        let json = serde_json::to_string(&contents).map_err(|e| e.to_string())?;
+        write_file_atomic(&path, &json).map_err(|e| e.to_string())
+    }
+
+    /// Read the file contents back from the operation's contents file
+    fn load_contents(&mut self, vault: &Path) -> Result<(), String> {
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        let json = fs::read_to_string(contents_path(vault, &self.id))
+            .map_err(|e| format!("Failed to read the contents of \"{}\": {}", self.label, e))?;
+        let contents: Vec<(Option<String>, Option<String>)> =
+            serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if contents.len() != self.files.len() {
+            return Err(format!("The contents of \"{}\" are incomplete", self.label));
+        }
+        for (file, (before, after)) in self.files.iter_mut().zip(contents) {
+            file.before = before;
+            file.after = after;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    operations: Vec<Operation>,
+}
+
+fn history_path(vault: &Path) -> PathBuf {
+    vault.join(APP_DIR).join(HISTORY_FILE)
+}
+
+fn contents_path(vault: &Path, id: &str) -> PathBuf {
+    vault
+        .join(APP_DIR)
+        .join(CONTENTS_DIR)
+        .join(format!("{}.json", id))
+}
+
+/// Delete the contents files of operations dropped from the history
+fn remove_contents(vault: &Path, operations: &[Operation]) {
+    for operation in operations {
+        let _ = fs::remove_file(contents_path(vault, &operation.id));
+    }
+}
+
+impl History {
+    fn load(vault: &Path) -> History {
+        fs::read_to_string(history_path(vault))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, vault: &Path) -> Result<(), String> {
+        let path = history_path(vault);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        write_file_atomic(&path, &json).map_err(|e| e.to_string())
+    }
+
+    /// The operation `redo_operation` applies: the first undone one after
+    /// the last one in effect, else the latest undone one
+    fn redo_candidate(&self) -> Option<usize> {
+        let active = self.operations.iter().rposition(|op| !op.undone);
+        let after = active.map_or(0, |i| i + 1);
+        if after < self.operations.len() {
+            Some(after)
+        } else {
+            self.operations.iter().rposition(|op| op.undone)
+        }
+    }
+}
+
+/// Path of a file relative to the vault, as stored in `FileChange`
+pub(crate) fn relative_path(vault: &Path, path: &Path) -> String {
+    path.strip_prefix(vault)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// The vault a file belongs to: the given vault, or else the nearest folder
+/// that has app data
+pub(crate) fn vault_for(vault_path: Option<&str>, path: &Path) -> Option<PathBuf> {
+    match vault_path {
+        Some(vault) => Some(PathBuf::from(vault)),
+        None => path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(APP_DIR).is_dir())
+            .map(Path::to_path_buf),
+    }
+}
+
+/// Folders from `dir` up to the vault that don't exist yet, outermost first
+pub(crate) fn missing_folders(vault: &Path, dir: &Path) -> Vec<String> {
+    let mut missing: Vec<String> = dir
+        .ancestors()
+        .take_while(|d| d.starts_with(vault) && *d != vault && !d.exists())
+        .map(|d| relative_path(vault, d))
+        .collect();
+    missing.reverse();
+    missing
+}
+
+/// Record an operation and return its ID. Operations that were undone can
+/// no longer be redone afterwards.
+pub(crate) fn record(vault: &Path, mut operation: Operation) -> Result<String, String> {
+    let mut history = History::load(vault);
+    let now = chrono::Utc::now();
+    let (undone, kept): (Vec<Operation>, Vec<Operation>) =
+        history.operations.into_iter().partition(|op| op.undone);
+    history.operations = kept;
+    remove_contents(vault, &undone);
+
+    // Consecutive saves of one note become a single operation
+    if operation.kind == OperationKind::WriteNote {
+        if let (Some(last), [change]) = (history.operations.last_mut(), &operation.files[..]) {
+            let same_note = last.kind == OperationKind::WriteNote
+                && last.files.len() == 1
+                && last.files[0].path == change.path
+                && now.timestamp() - last.timestamp <= EDIT_MERGE_SECONDS
+                && last.load_contents(vault).is_ok()
+                && last.files[0].after == change.before;
+            if same_note {
+                last.files[0].after = change.after.clone();
+                last.timestamp = now.timestamp();
+                let id = last.id.clone();
+                last.save_contents(vault)?;
+                history.save(vault)?;
+                return Ok(id);
+            }
+        }
+    }
+
+    let mut id = now.timestamp_millis().to_string();
+    while history.operations.iter().any(|op| op.id == id) {
+        id.push('0');
+    }
+    operation.id = id.clone();
+    operation.timestamp = now.timestamp();
+    operation.undone = false;
+    operation.save_contents(vault)?;
+
+    history.operations.push(operation);
+    let excess = history.operations.len().saturating_sub(MAX_OPERATIONS);
+    let dropped: Vec<Operation> = history.operations.drain(..excess).collect();
+    remove_contents(vault, &dropped);
+
+    history.save(vault)?;
+    Ok(id)
+}
+
+/// Collects the notes a command edits and records them as one operation.
+///
+/// The operation is recorded when the journal is finished or dropped, so
+/// files written before a failure can still be undone.
+pub(crate) struct Journal {
+    vault: Option<PathBuf>,
+    operation: Option<Operation>,
+}
+
+impl Journal {
+    pub(crate) fn new(vault: Option<PathBuf>, label: impl Into<String>) -> Journal {
+        Journal {
+            vault,
+            operation: Some(Operation::new(OperationKind::EditNotes, label)),
+        }
+    }
+
+    /// Write a note, remembering its previous content
+    pub(crate) fn write(&mut self, path: &Path, before: &str, after: &str) -> std::io::Result<()> {
+        write_file_atomic(path, after)?;
+        self.note(path, Some(before), Some(after));
+        Ok(())
+    }
+
+    /// Create a new note
+    pub(crate) fn create(&mut self, path: &Path, content: &str) -> std::io::Result<()> {
+        write_file_atomic(path, content)?;
+        self.note(path, None, Some(content));
+        Ok(())
+    }
+
+    /// Delete a note, keeping its content so it can be restored
+    pub(crate) fn remove(&mut self, path: &Path, before: &str) -> std::io::Result<()> {
+        fs::remove_file(path)?;
+        self.note(path, Some(before), None);
+        Ok(())
+    }
+
+    /// Move a file of any type, such as an attachment, without reading it
+    pub(crate) fn rename(&mut self, from: &Path, to: &Path) -> std::io::Result<()> {
+        fs::rename(from, to)?;
+        if let (Some(vault), Some(operation)) = (&self.vault, self.operation.as_mut()) {
+            operation
+                .moved_files
+                .push((relative_path(vault, from), relative_path(vault, to)));
+        }
+        Ok(())
+    }
+
+    fn note(&mut self, path: &Path, before: Option<&str>, after: Option<&str>) {
+        if let (Some(vault), Some(operation)) = (&self.vault, self.operation.as_mut()) {
+            operation.change(
+                vault,
+                path,
+                before.map(str::to_string),
+                after.map(str::to_string),
+            );
+        }
+    }
+
+    /// Write a single note and record the operation right away
+    pub(crate) fn finish_write(
+        mut self,
+        path: &Path,
+        before: &str,
+        after: &str,
+    ) -> Result<Option<String>, String> {
+        self.write(path, before, after)
+            .map_err(|e| format!("Failed to write note: {}", e))?;
+        self.finish()
+            .map_err(|e| format!("Written, but failed to save history: {}", e))
+    }
+
+    /// Record the operation, if anything changed; returns its ID
+    pub(crate) fn finish(mut self) -> Result<Option<String>, String> {
+        self.save()
+    }
+
+    fn save(&mut self) -> Result<Option<String>, String> {
+        match (&self.vault, self.operation.take()) {
+            (Some(vault), Some(operation))
+                if !operation.files.is_empty() || !operation.moved_files.is_empty() =>
+            {
+                record(vault, operation).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+/// Apply one side of an operation: its `before` state when undoing, its
+/// `after` state when redoing
+fn apply(vault: &Path, operation: &Operation, undo: bool) -> Result<Vec<String>, String> {
+    let target = |change: &FileChange| {
+        if undo {
+            change.before.clone()
+        } else {
+            change.after.clone()
+        }
+    };
+
+    if !undo {
+        for folder in &operation.folders {
+            fs::create_dir_all(vault.join(folder))
+                .map_err(|e| format!("Failed to create {}: {}", folder, e))?;
+        }
+    }
+
+    // Deletions first, so a rename that only changed case is not undone by
+    // deleting the file it just restored
+    let mut touched = Vec::new();
+    for change in operation.files.iter().filter(|c| target(c).is_none()) {
+        let path = vault.join(&change.path);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", change.path, e))?;
+        }
+        touched.push(path.to_string_lossy().to_string());
+    }
+    for change in &operation.files {
+        if let Some(content) = target(change) {
+            let path = vault.join(&change.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            write_file_atomic(&path, &content)
+                .map_err(|e| format!("Failed to restore {}: {}", change.path, e))?;
+            touched.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    // Undone in reverse so a file moved twice ends up where it started
+    let moved: Vec<&(String, String)> = if undo {
+        operation.moved_files.iter().rev().collect()
+    } else {
+        operation.moved_files.iter().collect()
+    };
+    for (from, to) in moved {
+        let (source, dest) = if undo { (to, from) } else { (from, to) };
+        let dest_path = vault.join(dest);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::rename(vault.join(source), &dest_path)
+            .map_err(|e| format!("Failed to move {}: {}", source, e))?;
+        touched.push(dest_path.to_string_lossy().to_string());
+    }
+
+    if undo {
+        // Innermost first; folders that are not empty are left alone
+        for folder in operation.folders.iter().rev() {
+            let _ = fs::remove_dir(vault.join(folder));
+        }
+    }
+
+    for (from, to) in &operation.moves {
+        let (old, new) = if undo { (to, from) } else { (from, to) };
+        note_ids::record_move(&vault.join(old), &vault.join(new));
+    }
+    Ok(touched)
+}
+
+/// Files whose current content is not what the operation left (`after`, or
+/// `before` once undone), i.e., that were edited since, and moved files that
+/// are no longer where it put them
+fn conflicts(vault: &Path, operation: &Operation, undo: bool) -> Vec<String> {
+    let edited = operation
+        .files
+        .iter()
+        .filter(|change| {
+            let expected = if undo { &change.after } else { &change.before };
+            fs::read_to_string(vault.join(&change.path)).ok() != *expected
+        })
+        .map(|change| change.path.clone());
+    let moved = operation
+        .moved_files
+        .iter()
+        .filter(|(from, to)| {
+            let (source, dest) = if undo { (to, from) } else { (from, to) };
+            !vault.join(source).exists() || vault.join(dest).exists()
+        })
+        .map(|(from, _)| from.clone());
+    edited.chain(moved).collect()
+}
+
+/// Result of undoing or redoing an operation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UndoResult {
+    pub id: String,
+    pub label: String,
+    /// Files put back in the state the operation was undone or redone to
+    pub restored: Vec<String>,
+}
+
+/// Undo or redo the operation at `index` of the history
+fn undo_or_redo(
+    vault: &Path,
+    mut history: History,
+    index: usize,
+    undo: bool,
+    force: bool,
+) -> FsResult<UndoResult> {
+    let mut operation = history.operations[index].clone();
+    if let Err(e) = operation.load_contents(vault) {
+        return FsResult::err(&e);
+    }
+    let changed = conflicts(vault, &operation, undo);
+    if !changed.is_empty() && !force {
+        return FsResult::err(&format!(
+            "Files changed since the operation: {}",
+            changed.join(", ")
+        ));
+    }
+
+    let restored = match apply(vault, &operation, undo) {
+        Ok(restored) => restored,
+        Err(e) => return FsResult::err(&e),
+    };
+    let result = UndoResult {
+        id: operation.id,
+        label: operation.label,
+        restored,
+    };
+
+    history.operations[index].undone = undo;
+    if let Err(e) = history.save(vault) {
+        return FsResult::err(&format!("Failed to save history: {}", e));
+    }
+    FsResult::ok(result)
+}
+
+/// Undo a recorded operation, restoring every file it touched - Tauri command
+///
+/// Fails without changing anything when one of the files was edited after
+/// the operation, unless `force` is set.
+#[tauri::command]
+pub fn undo_operation(vault_path: String, id: String, force: Option<bool>) -> FsResult<UndoResult> {
+    let vault = PathBuf::from(&vault_path);
+    let history = History::load(&vault);
+
+    match history.operations.iter().position(|op| op.id == id) {
+        Some(i) if history.operations[i].undone => FsResult::err("Operation was already undone"),
+        Some(i) => undo_or_redo(&vault, history, i, true, force.unwrap_or(false)),
+        None => FsResult::err("Operation not found"),
+    }
+}
+
+/// Undo the most recent operation that is still in effect - Tauri command
+#[tauri::command]
+pub fn undo_last_operation(vault_path: String, force: Option<bool>) -> FsResult<UndoResult> {
+    let vault = PathBuf::from(&vault_path);
+    let history = History::load(&vault);
+
+    match history.operations.iter().rposition(|op| !op.undone) {
+        Some(i) => undo_or_redo(&vault, history, i, true, force.unwrap_or(false)),
+        None => FsResult::err("Nothing to undo"),
+    }
+}
+
+/// Redo the most recently undone operation - Tauri command
+///
+/// Like undo, fails when a file changed in the meantime unless `force` is set.
+#[tauri::command]
+pub fn redo_operation(vault_path: String, force: Option<bool>) -> FsResult<UndoResult> {
+    let vault = PathBuf::from(&vault_path);
+    let history = History::load(&vault);
+
+    match history.redo_candidate() {
+        Some(i) => undo_or_redo(&vault, history, i, false, force.unwrap_or(false)),
+        None => FsResult::err("Nothing to redo"),
+    }
+}
+
+/// An operation in the history list
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OperationSummary {
+    pub id: String,
+    pub kind: OperationKind,
+    pub label: String,
+    pub timestamp: i64,
+    /// Files the operation touched, relative to the vault
+    pub files: Vec<String>,
+    pub undone: bool,
+    /// Whether `redo_operation` would apply this operation next
+    pub next_redo: bool,
+}
+
+/// List recorded operations, newest first - Tauri command
+#[tauri::command]
+pub fn get_operation_history(vault_path: String) -> FsResult<Vec<OperationSummary>> {
+    let vault = PathBuf::from(&vault_path);
+    let history = History::load(&vault);
+    let next_redo = history.redo_candidate();
+
+    let summaries = history
+        .operations
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, op)| OperationSummary {
+            id: op.id.clone(),
+            kind: op.kind,
+            label: op.label.clone(),
+            timestamp: op.timestamp,
+            files: op
+                .files
+                .iter()
+                .map(|f| f.path.clone())
+                .chain(op.moved_files.iter().map(|(from, _)| from.clone()))
+                .collect(),
+            undone: op.undone,
+            next_redo: next_redo == Some(i),
+        })
+        .collect();
+    FsResult::ok(summaries)
+}
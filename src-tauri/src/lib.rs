@@ -6,6 +6,30 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::Manager;
 
+mod aliases;
+mod attachments;
+mod blocks;
+mod diagrams;
+mod duplicates;
+mod embeds;
+mod find_replace;
+mod frontmatter;
+mod graph;
+mod headings;
+mod history;
+mod links;
+mod markdown;
+mod math;
+mod mentions;
+mod note_ids;
+mod outline;
+mod periodic;
+mod refactor;
+mod reminders;
+mod similarity;
+mod tasks;
+mod templates;
+
 /// Represents a note file
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Note {
@@ -39,16 +63,34 @@ pub struct WikiLink {
     pub target: String,
     /// Optional heading anchor (e.g., "section" from [[note#section]])
     pub heading: Option<String>,
+    /// Optional block reference (e.g., "abc123" from [[note#^abc123]])
+    pub block_id: Option<String>,
     /// Optional display text (e.g., "My Note" from [[note|My Note]])
     pub display_text: Option<String>,
+    /// Whether this is an embed (`![[note]]`) rather than a link
+    pub is_embed: bool,
+    /// Whether this is a Markdown link (`[text](note.md)`); its target is then
+    /// a decoded path relative to the linking note, without `.md`
+    pub is_markdown: bool,
     /// Start position in the original content (byte offset)
     pub start: usize,
     /// End position in the original content (byte offset)
     pub end: usize,
-    /// The raw matched string including brackets
+    /// The raw matched string including brackets (and the `!` of an embed)
     pub raw: String,
 }
 
+impl WikiLink {
+    /// The part after `#`: the heading, or `^id` for a block reference
+    pub fn anchor(&self) -> Option<String> {
+        match (&self.heading, &self.block_id) {
+            (Some(heading), _) => Some(heading.clone()),
+            (None, Some(block_id)) => Some(format!("^{}", block_id)),
+            (None, None) => None,
+        }
+    }
+}
+
 /// Result of parsing links from content
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ParsedLinks {
@@ -92,6 +134,8 @@ pub struct FileTree {
 struct AppConfig {
     current_vault: Option<String>,
     recent_vaults: Vec<String>,
+    // Notes are stored by stable ID when the app exits (see `save_session`);
+    // paths, as saved in between and by older versions, are still understood
     #[serde(default)]
     open_notes_per_vault: HashMap<String, Vec<String>>,
     #[serde(default)]
@@ -99,6 +143,10 @@ struct AppConfig {
     #[serde(default)]
     last_note_per_vault: HashMap<String, String>,  // Kept for migration
     last_open_directory: Option<String>,
+    #[serde(default)]
+    periodic_notes_per_vault: HashMap<String, periodic::PeriodicNotesConfig>,
+    #[serde(default)]
+    task_notifications: bool,
 }
 
 impl Default for AppConfig {
@@ -110,6 +158,8 @@ impl Default for AppConfig {
             active_note_per_vault: HashMap::new(),
             last_note_per_vault: HashMap::new(),
             last_open_directory: None,
+            periodic_notes_per_vault: HashMap::new(),
+            task_notifications: false,
         }
     }
 }
@@ -123,6 +173,38 @@ pub struct BacklinkInfo {
     pub source_name: String,
     /// Links from the source note that point to the target
     pub links: Vec<WikiLink>,
+    /// Blocks of the target note referenced by `[[note#^id]]` links
+    pub referenced_blocks: Vec<blocks::Block>,
+    /// Where each of `links` appears in the source note (same order)
+    pub contexts: Vec<BacklinkContext>,
+    /// Folder of the source note relative to the vault ("" for the root)
+    pub folder: String,
+    pub modified: u64,
+}
+
+/// The text around a backlink
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BacklinkContext {
+    /// Line number of the link (1-based)
+    pub line: usize,
+    /// Headings enclosing the link, outermost first
+    pub heading_path: Vec<String>,
+    /// The paragraph or list item containing the link
+    pub text: String,
+    /// Byte offset of `text` in the source note
+    pub start: usize,
+    /// Byte offset of the end of `text`
+    pub end: usize,
+}
+
+/// Order of `get_backlinks` results
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BacklinkSort {
+    /// Most recently modified source note first
+    Modified,
+    /// By folder, then note name
+    Folder,
 }
 
 /// Result type for file operations
@@ -226,10 +308,10 @@ pub struct ThemeSyntaxColors {
     pub heading4: String,
     pub link: String,
     pub code: String,
-    #[serde(default)]
+    #[serde(default, alias = "codeBg")]
     pub code_bg: String,
     pub quote: String,
-    #[serde(default)]
+    #[serde(default, alias = "quoteBg")]
     pub quote_bg: String,
     pub hr: String,
 }
@@ -252,38 +334,315 @@ fn get_themes_dir(app_handle: &tauri::AppHandle) -> std::io::Result<PathBuf> {
     Ok(config_dir.join("themes"))
 }
 
+/// Byte ranges of content that is not markdown text: fenced code blocks,
+/// inline code spans and HTML comments
+fn non_text_ranges(content: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+
+    // Fenced code blocks, whole lines (fences included)
+    let in_code = fenced_line_mask(content);
+    let mut offset = 0;
+    for (i, line) in content.split('\n').enumerate() {
+        let end = (offset + line.len() + 1).min(content.len());
+        if in_code.get(i).copied().unwrap_or(false) {
+            match ranges.last_mut() {
+                Some((_, last_end)) if *last_end == offset => *last_end = end,
+                _ => ranges.push((offset, end)),
+            }
+        }
+        offset = end;
+    }
+
+    let bytes = content.as_bytes();
+    let mut spans = Vec::new();
+    let mut fenced = ranges.iter().peekable();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some((start, end)) = fenced.peek() {
+            if i >= *start {
+                i = i.max(*end);
+                fenced.next();
+                continue;
+            }
+        }
+
+        match bytes[i] {
+            // An escaped character is plain text
+            b'\\' => i += 2,
+            b'<' if bytes[i..].starts_with(b"<!--") => {
+                let end = content[i + 4..].find("-->").map_or(content.len(), |e| i + 4 + e + 3);
+                spans.push((i, end));
+                i = end;
+            }
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                // The span closes at the next run of exactly the same length
+                let mut j = i + run;
+                let mut close = None;
+                while j < bytes.len() {
+                    if bytes[j] == b'`' {
+                        let len = bytes[j..].iter().take_while(|b| **b == b'`').count();
+                        if len == run {
+                            close = Some(j + len);
+                            break;
+                        }
+                        j += len;
+                    } else {
+                        j += 1;
+                    }
+                }
+                match close {
+                    Some(end) => {
+                        spans.push((i, end));
+                        i = end;
+                    }
+                    None => i += run,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    ranges.extend(spans);
+    ranges.sort();
+    ranges
+}
+
+/// Split a link target into the note and its `#heading` / `#^block` anchor.
+///
+/// A `#` only starts an anchor when followed by a non-space character, so
+/// file names like "C# basics" stay whole while "note#Intro" is split.
+fn split_link_anchor(target: &str) -> (&str, Option<&str>) {
+    let bytes = target.as_bytes();
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == b'#' && bytes.get(i + 1).is_some_and(|n| !n.is_ascii_whitespace() && *n != b'#') {
+            return (&target[..i], Some(&target[i + 1..]));
+        }
+    }
+    (target, None)
+}
+
+/// Parse the inside of `[[...]]` into a link, or `None` if it is not one
+fn parse_wiki_link_inner(content: &str, start: usize, end: usize, is_embed: bool) -> Option<WikiLink> {
+    let open = if is_embed { start + 3 } else { start + 2 };
+    let inner = &content[open..end - 2];
+
+    let (target_part, display_text) = match inner.split_once('|') {
+        // Inside tables the pipe is escaped: [[note\|alias]]
+        Some((t, d)) => (t.strip_suffix('\\').unwrap_or(t), Some(d.trim().to_string())),
+        None => (inner, None),
+    };
+
+    let (target, anchor) = split_link_anchor(target_part);
+    let target = target.trim().to_string();
+
+    // Skip empty targets
+    if target.is_empty() {
+        return None;
+    }
+
+    // [[note#^id]] references a block rather than a heading
+    let anchor = anchor.map(|a| a.trim()).filter(|a| !a.is_empty());
+    let (heading, block_id) = match anchor {
+        Some(a) if a.starts_with('^') => (None, Some(a[1..].trim().to_string())),
+        other => (other.map(|a| a.to_string()), None),
+    };
+
+    Some(WikiLink {
+        target,
+        heading,
+        block_id,
+        display_text,
+        is_embed,
+        is_markdown: false,
+        start,
+        end,
+        raw: content[start..end].to_string(),
+    })
+}
+
 /// Parse wiki links from markdown content
+///
+/// Matches [[target]], [[target#heading]], [[target#^block]], [[target|display]],
+/// [[target#heading|display]], each optionally prefixed with ! for an embed
+/// (e.g., ![[target]]). Links inside code, HTML comments or written with
+/// escaped brackets (`\[\[not a link\]\]`) are ignored.
 fn parse_wiki_links(content: &str) -> Vec<WikiLink> {
-    // Pattern matches: [[target]], [[target#heading]], [[target|display]], [[target#heading|display]]
-    let pattern = r"\[\[([^\]|#]+)(?:#([^|\]]+))?(?:\|([^\]]+))?\]\]";
+    let bytes = content.as_bytes();
+    let skipped = non_text_ranges(content);
+    let mut skipped = skipped.iter().peekable();
+    let mut links = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some((start, end)) = skipped.peek() {
+            if i >= *start {
+                i = i.max(*end);
+                skipped.next();
+                continue;
+            }
+        }
+
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if !bytes[i..].starts_with(b"[[") {
+            i += 1;
+            continue;
+        }
+
+        // Find the closing ]] on the same line; another [[ first means this one is not a link
+        let limit = skipped.peek().map_or(bytes.len(), |(start, _)| *start);
+        let mut j = i + 2;
+        let mut close = None;
+        while j + 1 < limit && bytes[j] != b'\n' {
+            if bytes[j] == b'[' && bytes[j + 1] == b'[' {
+                break;
+            }
+            if bytes[j] == b']' && bytes[j + 1] == b']' {
+                let mut end = j + 2;
+                // Display text may itself end in ]: [[note|see [1]]]
+                if content[i + 2..j].contains('|') {
+                    while end < limit && bytes[end] == b']' {
+                        end += 1;
+                    }
+                }
+                close = Some(end);
+                break;
+            }
+            j += 1;
+        }
+
+        let end = match close {
+            Some(end) => end,
+            None => {
+                i += 2;
+                continue;
+            }
+        };
+
+        let is_embed = i > 0 && bytes[i - 1] == b'!';
+        let start = if is_embed { i - 1 } else { i };
+        if let Some(link) = parse_wiki_link_inner(content, start, end, is_embed) {
+            links.push(link);
+        }
+        i = end;
+    }
+
+    links
+}
+
+/// A Markdown link or image (`[text](url)`, `![alt](url)`) in note text, with
+/// its URL not yet interpreted
+pub(crate) struct MarkdownLinkMatch<'a> {
+    pub is_embed: bool,
+    pub text: &'a str,
+    /// The URL without angle brackets or title, still percent-encoded
+    pub url: &'a str,
+    pub start: usize,
+    pub end: usize,
+    pub raw: &'a str,
+}
+
+/// Find Markdown links with local URLs, skipping escaped links, external
+/// URLs, in-page anchors and anything inside code or HTML comments
+pub(crate) fn markdown_link_matches(content: &str) -> Vec<MarkdownLinkMatch<'_>> {
+    // Pattern matches: [text](url), ![alt](url), [text](<url with spaces> "title");
+    // the text may contain one level of brackets: [see [1]](note.md)
+    let pattern = r#"(!)?\[((?:[^\[\]\n]|\[[^\]\n]*\])*)\]\(\s*(<[^>\n]+>|[^)\s]+)(?:\s+"[^"\n]*")?\s*\)"#;
     let re = Regex::new(pattern).unwrap();
+    let skipped = non_text_ranges(content);
 
     re.captures_iter(content)
         .filter_map(|cap| {
             let full_match = cap.get(0)?;
-            let target = cap.get(1)?.as_str().trim().to_string();
+            let start = full_match.start();
+
+            // Escaped (\[text](url)) or part of a wiki link ([[a]](b))
+            let before = content[..start].chars().next_back();
+            if before == Some('\\') || before == Some('[') {
+                return None;
+            }
+            if skipped.iter().any(|(s, e)| *s <= start && start < *e) {
+                return None;
+            }
 
-            // Skip empty targets
-            if target.is_empty() {
+            let url = cap.get(3)?.as_str().trim_start_matches('<').trim_end_matches('>');
+            if url.contains("://") || url.starts_with("mailto:") || url.starts_with('#') {
                 return None;
             }
 
+            Some(MarkdownLinkMatch {
+                is_embed: cap.get(1).is_some(),
+                text: cap.get(2)?.as_str(),
+                url,
+                start,
+                end: full_match.end(),
+                raw: full_match.as_str(),
+            })
+        })
+        .collect()
+}
+
+/// Parse Markdown links to notes (e.g., `[text](other%20note.md#section)`)
+///
+/// Targets are percent-decoded and keep their relative path without `.md`.
+/// External URLs, in-page anchors and links to attachments are skipped, as
+/// are links inside code and HTML comments.
+fn parse_markdown_links(content: &str) -> Vec<WikiLink> {
+    markdown_link_matches(content)
+        .into_iter()
+        .filter_map(|m| {
+            let (path, fragment) = match m.url.split_once('#') {
+                Some((p, f)) => (decode_percent(p), Some(decode_percent(f))),
+                None => (decode_percent(m.url), None),
+            };
+
+            // Only notes: "note.md" or an extension-less path
+            let target = match path.strip_suffix(".md") {
+                Some(t) => t.to_string(),
+                None if std::path::Path::new(&path).extension().is_none() => path.clone(),
+                None => return None,
+            };
+            if target.trim().is_empty() {
+                return None;
+            }
+
+            let fragment = fragment.map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+            let (heading, block_id) = match fragment {
+                Some(f) if f.starts_with('^') => (None, Some(f[1..].to_string())),
+                other => (other, None),
+            };
+
             Some(WikiLink {
                 target,
-                heading: cap.get(2).map(|m| m.as_str().trim().to_string()),
-                display_text: cap.get(3).map(|m| m.as_str().trim().to_string()),
-                start: full_match.start(),
-                end: full_match.end(),
-                raw: full_match.as_str().to_string(),
+                heading,
+                block_id,
+                display_text: Some(m.text.to_string()).filter(|t| !t.is_empty()),
+                is_embed: m.is_embed,
+                is_markdown: true,
+                start: m.start,
+                end: m.end,
+                raw: m.raw.to_string(),
             })
         })
         .collect()
 }
 
+/// Parse both wiki links and Markdown links to notes, in document order
+fn parse_note_links(content: &str) -> Vec<WikiLink> {
+    let mut links = parse_wiki_links(content);
+    links.extend(parse_markdown_links(content));
+    links.sort_by_key(|l| l.start);
+    links
+}
+
 /// Parse links from content - Tauri command
 #[tauri::command]
 fn parse_links(content: String) -> ParsedLinks {
-    let links = parse_wiki_links(&content);
+    let links = parse_note_links(&content);
     let referenced_notes: Vec<String> = links
         .iter()
         .map(|l| l.target.clone())
@@ -297,22 +656,45 @@ fn parse_links(content: String) -> ParsedLinks {
     }
 }
 
-/// Get backlinks for a note - Tauri command
+/// Context of a link in its note: line, enclosing headings and paragraph
+fn backlink_context(content: &str, headings: &[headings::Heading], link: &WikiLink) -> BacklinkContext {
+    let (start, end) = blocks::block_range(content, link.start);
+    BacklinkContext {
+        line: links::line_at(content, link.start),
+        heading_path: headings::heading_path(headings, link.start),
+        text: content[start..end].to_string(),
+        start,
+        end,
+    }
+}
+
+/// Get backlinks for a note, with the context of each link - Tauri command
 #[tauri::command]
-fn get_backlinks(vault_path: String, note_name: String) -> FsResult<Vec<BacklinkInfo>> {
+fn get_backlinks(
+    vault_path: String,
+    note_path: String,
+    sort: Option<BacklinkSort>,
+) -> FsResult<Vec<BacklinkInfo>> {
     let vault = PathBuf::from(&vault_path);
 
     if !vault.exists() {
         return FsResult::ok(vec![]);
     }
 
-    let target_name = note_name.to_lowercase();
     let mut backlinks: Vec<BacklinkInfo> = Vec::new();
 
+    let index = NoteIndex::build(&vault);
+    let target_note = match index.position(std::path::Path::new(&note_path)) {
+        Some(i) => i,
+        None => return FsResult::ok(vec![]),
+    };
+
     // Recursive function to scan all markdown files
     fn scan_for_backlinks(
+        vault: &PathBuf,
         dir: &PathBuf,
-        target_name: &str,
+        index: &NoteIndex,
+        target: usize,
         backlinks: &mut Vec<BacklinkInfo>,
     ) {
         if let Ok(entries) = fs::read_dir(dir) {
@@ -325,35 +707,48 @@ fn get_backlinks(vault_path: String, note_name: String) -> FsResult<Vec<Backlink
                 }
 
                 if entry_path.is_dir() {
-                    scan_for_backlinks(&entry_path, target_name, backlinks);
+                    scan_for_backlinks(vault, &entry_path, index, target, backlinks);
                 } else if entry_path.extension().map_or(false, |ext| ext == "md") {
-                    // Skip the note itself (by name)
+                    // Skip the note itself
+                    if entry_path == index.notes[target] {
+                        continue;
+                    }
+
                     let file_stem = entry_path
                         .file_stem()
                         .map(|s| s.to_string_lossy().to_string())
                         .unwrap_or_default();
 
-                    if file_stem.to_lowercase() == target_name {
-                        continue;
-                    }
-
                     // Read and parse the file
                     if let Ok(content) = fs::read_to_string(&entry_path) {
-                        let links = parse_wiki_links(&content);
+                        let links = parse_note_links(&content);
                         let matching_links: Vec<WikiLink> = links
                             .into_iter()
-                            .filter(|l| {
-                                // Match by note name (strip folder path if present)
-                                let link_target = l.target.rsplit('/').next().unwrap_or(&l.target);
-                                link_target.to_lowercase() == target_name.to_lowercase()
-                            })
+                            // Only links that resolve to the note, not to another
+                            // note of the same name
+                            .filter(|l| index.resolve_link(l, &entry_path) == Some(target))
                             .collect();
 
                         if !matching_links.is_empty() {
+                            let headings = headings::parse_headings(&content);
+                            let contexts = matching_links
+                                .iter()
+                                .map(|l| backlink_context(&content, &headings, l))
+                                .collect();
+                            let folder = entry_path
+                                .parent()
+                                .and_then(|p| p.strip_prefix(vault).ok())
+                                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                                .unwrap_or_default();
+
                             backlinks.push(BacklinkInfo {
                                 source_path: entry_path.to_string_lossy().to_string(),
                                 source_name: file_stem,
                                 links: matching_links,
+                                referenced_blocks: Vec::new(),
+                                contexts,
+                                folder,
+                                modified: get_modified_time(&entry_path),
                             });
                         }
                     }
@@ -362,71 +757,341 @@ fn get_backlinks(vault_path: String, note_name: String) -> FsResult<Vec<Backlink
         }
     }
 
-    scan_for_backlinks(&vault, &target_name, &mut backlinks);
+    scan_for_backlinks(&vault, &vault, &index, target_note, &mut backlinks);
+
+    // Report which blocks of the target note are referenced
+    if backlinks.iter().any(|b| b.links.iter().any(|l| l.block_id.is_some())) {
+        let target_blocks = fs::read_to_string(&index.notes[target_note])
+            .ok()
+            .map(|content| blocks::parse_blocks(&content))
+            .unwrap_or_default();
+
+        for backlink in &mut backlinks {
+            for link in &backlink.links {
+                let block = link
+                    .block_id
+                    .as_deref()
+                    .and_then(|id| blocks::find_block(&target_blocks, id));
+                if let Some(block) = block {
+                    if !backlink.referenced_blocks.iter().any(|b| b.id == block.id) {
+                        backlink.referenced_blocks.push(block.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    match sort {
+        Some(BacklinkSort::Modified) => backlinks.sort_by_key(|b| std::cmp::Reverse(b.modified)),
+        Some(BacklinkSort::Folder) => backlinks.sort_by(|a, b| {
+            (&a.folder, a.source_name.to_lowercase()).cmp(&(&b.folder, b.source_name.to_lowercase()))
+        }),
+        None => {}
+    }
+
     FsResult::ok(backlinks)
 }
 
 /// Resolve a wiki link target to a file path - Tauri command
+///
+/// When several notes share the name, an explicit folder path wins, then a
+/// note in the linking note's folder (`source_path`), then the shortest path.
+/// Frontmatter aliases are consulted when no note has the name.
 #[tauri::command]
-fn resolve_wiki_link(vault_path: String, target: String) -> FsResult<Option<String>> {
+fn resolve_wiki_link(
+    vault_path: String,
+    target: String,
+    source_path: Option<String>,
+) -> FsResult<Option<String>> {
     let vault = PathBuf::from(&vault_path);
 
-    // Parse folder path from target if present (e.g., "folder/note")
-    let (folder, note_name) = if target.contains('/') {
-        let parts: Vec<&str> = target.rsplitn(2, '/').collect();
-        if parts.len() == 2 {
-            (Some(parts[1].to_string()), parts[0].to_string())
-        } else {
-            (None, parts[0].to_string())
+    // Ignore any heading or block anchor (e.g., "note#section", "note#^abc123")
+    let target = split_link_anchor(&target).0.trim().to_string();
+
+    let source = source_path.map(PathBuf::from);
+
+    // A note in the linking note's folder, or at the exact path from the vault
+    // root, is the best match; only build the index when neither exists
+    let note_name = target.trim_end_matches(".md").trim_matches('/').replace('\\', "/");
+    let source_dir = source
+        .as_deref()
+        .and_then(|s| s.parent())
+        .filter(|_| !target.contains('/'));
+    let direct = source_dir
+        .into_iter()
+        .chain(std::iter::once(vault.as_path()))
+        .find_map(|dir| existing_note(&vault, dir, &note_name));
+    if let Some(path) = direct {
+        return FsResult::ok(Some(path.to_string_lossy().to_string()));
+    }
+
+    let index = NoteIndex::build(&vault);
+    let result = index.resolve_from(&target, source.as_deref());
+    FsResult::ok(result.map(|i| index.notes[i].to_string_lossy().to_string()))
+}
+
+/// The note `name` in `dir` if it exists with exactly that name and is not
+/// outside the vault or in a hidden folder
+fn existing_note(vault: &std::path::Path, dir: &std::path::Path, name: &str) -> Option<PathBuf> {
+    if name.is_empty() {
+        return None;
+    }
+    let path = normalize_path(&dir.join(format!("{}.md", name)));
+    let relative = path.strip_prefix(vault).ok()?;
+    if relative.iter().any(is_hidden) {
+        return None;
+    }
+
+    // Compare against the directory listing so case-insensitive file systems
+    // don't return the link's spelling instead of the note's
+    let file_name = path.file_name()?;
+    fs::read_dir(path.parent()?)
+        .ok()?
+        .flatten()
+        .any(|entry| entry.file_name() == file_name && entry.path().is_file())
+        .then_some(path)
+}
+
+/// Every note a link target may refer to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkCandidates {
+    /// The note the link resolves to
+    pub path: Option<String>,
+    /// All matching notes, best match first
+    pub candidates: Vec<String>,
+    /// More than one note matches
+    pub ambiguous: bool,
+}
+
+/// List every note a wiki link target may refer to, in resolution order - Tauri command
+#[tauri::command]
+fn resolve_link_candidates(
+    vault_path: String,
+    target: String,
+    source_path: Option<String>,
+) -> FsResult<LinkCandidates> {
+    let vault = PathBuf::from(&vault_path);
+    let target = split_link_anchor(&target).0.trim().to_string();
+
+    let index = NoteIndex::build(&vault);
+    let source = source_path.map(PathBuf::from);
+    let candidates: Vec<String> = index
+        .candidates(&target, source.as_deref())
+        .into_iter()
+        .map(|i| index.notes[i].to_string_lossy().to_string())
+        .collect();
+
+    FsResult::ok(LinkCandidates {
+        path: candidates.first().cloned(),
+        ambiguous: candidates.len() > 1,
+        candidates,
+    })
+}
+
+/// Index of every note in a vault, for resolving many link targets without
+/// re-walking the file system
+pub(crate) struct NoteIndex {
+    pub vault: PathBuf,
+    /// All notes, sorted by path
+    pub notes: Vec<PathBuf>,
+    /// Lowercase note name -> indices into `notes`
+    by_name: HashMap<String, Vec<usize>>,
+    /// Lowercase relative path without .md (e.g., "folder/note") -> index
+    by_relative: HashMap<String, usize>,
+    /// Frontmatter aliases, read on first use since that opens every note
+    aliases: std::cell::OnceCell<NoteAliases>,
+}
+
+struct NoteAliases {
+    /// Aliases of each note
+    of_note: Vec<Vec<String>>,
+    /// Lowercase alias -> indices into `notes`
+    by_alias: HashMap<String, Vec<usize>>,
+}
+
+impl NoteIndex {
+    pub fn build(vault: &PathBuf) -> NoteIndex {
+        let notes = collect_notes(vault);
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_relative: HashMap<String, usize> = HashMap::new();
+
+        for (i, path) in notes.iter().enumerate() {
+            if let Some(stem) = path.file_stem() {
+                by_name
+                    .entry(stem.to_string_lossy().to_lowercase())
+                    .or_default()
+                    .push(i);
+            }
+            let relative = path
+                .strip_prefix(vault)
+                .unwrap_or(path)
+                .with_extension("")
+                .to_string_lossy()
+                .replace('\\', "/")
+                .to_lowercase();
+            by_relative.insert(relative, i);
         }
-    } else {
-        (None, target.clone())
-    };
 
-    // If folder specified, try exact path first
-    if let Some(folder_path) = &folder {
-        let exact_path = vault.join(folder_path).join(format!("{}.md", note_name));
-        if exact_path.exists() {
-            return FsResult::ok(Some(exact_path.to_string_lossy().to_string()));
+        NoteIndex {
+            vault: vault.clone(),
+            notes,
+            by_name,
+            by_relative,
+            aliases: std::cell::OnceCell::new(),
         }
     }
 
-    // Try exact match at vault root
-    let exact_path = vault.join(format!("{}.md", note_name));
-    if exact_path.exists() {
-        return FsResult::ok(Some(exact_path.to_string_lossy().to_string()));
+    fn alias_table(&self) -> &NoteAliases {
+        self.aliases.get_or_init(|| {
+            let mut by_alias: HashMap<String, Vec<usize>> = HashMap::new();
+            let of_note: Vec<Vec<String>> = self
+                .notes
+                .iter()
+                .map(|path| {
+                    fs::read_to_string(path)
+                        .map(|content| aliases::note_aliases(&content))
+                        .unwrap_or_default()
+                })
+                .collect();
+            for (i, note_aliases) in of_note.iter().enumerate() {
+                for alias in note_aliases {
+                    by_alias.entry(alias.to_lowercase()).or_default().push(i);
+                }
+            }
+            NoteAliases { of_note, by_alias }
+        })
     }
 
-    // Recursive search function
-    fn find_note_recursive(dir: &PathBuf, target_name: &str) -> Option<PathBuf> {
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
+    /// Frontmatter aliases of an indexed note
+    pub fn aliases(&self, index: usize) -> &[String] {
+        &self.alias_table().of_note[index]
+    }
 
-                // Skip hidden files/folders
-                if path.file_name().map_or(true, |n| is_hidden(n)) {
-                    continue;
-                }
+    /// Note name (file stem) of an indexed note
+    pub fn name(&self, index: usize) -> String {
+        self.notes[index]
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
 
-                if path.is_dir() {
-                    if let Some(found) = find_note_recursive(&path, target_name) {
-                        return Some(found);
-                    }
-                } else if path.extension().map_or(false, |ext| ext == "md") {
-                    if let Some(stem) = path.file_stem() {
-                        if stem.to_string_lossy().to_lowercase() == target_name.to_lowercase() {
-                            return Some(path);
-                        }
-                    }
-                }
+    /// Notes whose name is `name` (case-insensitive), ignoring aliases
+    pub fn named(&self, name: &str) -> Vec<usize> {
+        self.by_name
+            .get(&name.trim().to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Path of an indexed note relative to the vault root, with `/` separators
+    pub fn relative_path(&self, index: usize) -> String {
+        self.notes[index]
+            .strip_prefix(&self.vault)
+            .unwrap_or(&self.notes[index])
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Index of a note by its path
+    pub fn position(&self, path: &std::path::Path) -> Option<usize> {
+        self.notes.iter().position(|p| p == path)
+    }
+
+    /// Resolve a parsed link from the note at `source`. Markdown links are
+    /// relative to the linking note first, then the vault root.
+    pub fn resolve_link(&self, link: &WikiLink, source: &std::path::Path) -> Option<usize> {
+        if !link.is_markdown {
+            return self.resolve_from(&link.target, Some(source));
+        }
+
+        let relative = format!("{}.md", link.target.trim_start_matches('/'));
+        let note_dir = source.parent().unwrap_or(&self.vault);
+        let from_note = (!link.target.starts_with('/')).then(|| note_dir.join(&relative));
+
+        from_note
+            .into_iter()
+            .chain(std::iter::once(self.vault.join(&relative)))
+            .find_map(|candidate| self.position(&normalize_path(&candidate)))
+    }
+
+    /// Notes a link target may refer to, best match first.
+    ///
+    /// A full path from the vault root ("folder/note", or "/note" for a note
+    /// at the root) is explicit and wins; a partial path ("sub/note")
+    /// matches notes ending in it. Among notes
+    /// sharing a name, one in the linking note's folder comes first, then
+    /// shorter paths, then alphabetical order. A frontmatter alias is used
+    /// only when no note has the name.
+    pub fn candidates(&self, target: &str, source: Option<&std::path::Path>) -> Vec<usize> {
+        let explicit = target.contains('/');
+        let target = target
+            .trim()
+            .trim_end_matches(".md")
+            .trim_matches('/')
+            .replace('\\', "/")
+            .to_lowercase();
+        let note_name = target.rsplit('/').next().unwrap_or(&target);
+
+        if explicit {
+            if let Some(i) = self.by_relative.get(&target) {
+                return vec![*i];
             }
         }
-        None
+
+        let suffix = format!("/{}", target);
+        let mut found: Vec<usize> = self
+            .by_name
+            .get(note_name)
+            .map(|v| {
+                v.iter()
+                    .copied()
+                    .filter(|i| {
+                        !target.contains('/')
+                            || self.relative_path(*i).to_lowercase().trim_end_matches(".md").ends_with(&suffix)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Aliases only apply when no note has the name
+        if found.is_empty() {
+            found = self.alias_table().by_alias.get(&target).cloned().unwrap_or_default();
+        }
+
+        let source_dir = source.and_then(|s| s.parent());
+        found.sort_by_key(|i| {
+            let path = &self.notes[*i];
+            (path.parent() != source_dir, path.components().count(), path.clone())
+        });
+        found
+    }
+
+    /// Resolve a link target written in the note at `source` (see `candidates`)
+    pub fn resolve_from(&self, target: &str, source: Option<&std::path::Path>) -> Option<usize> {
+        self.candidates(target, source).first().copied()
+    }
+
+    /// Resolve a link target without a linking note: an explicit path, else
+    /// the note with that name that has the shortest path
+    pub fn resolve(&self, target: &str) -> Option<usize> {
+        self.resolve_from(target, None)
     }
 
-    // Fall back to recursive search
-    let result = find_note_recursive(&vault, &note_name);
-    FsResult::ok(result.map(|p| p.to_string_lossy().to_string()))
+    /// Shortest link target that refers to a note unambiguously from any
+    /// folder: its name if unique, else the shortest distinguishing path
+    pub fn link_target(&self, index: usize) -> String {
+        let relative = self.relative_path(index);
+        let relative = relative.trim_end_matches(".md");
+        let parts: Vec<&str> = relative.split('/').collect();
+
+        (1..=parts.len())
+            .map(|k| parts[parts.len() - k..].join("/"))
+            .find(|candidate| self.candidates(candidate, None) == vec![index])
+            .unwrap_or_else(|| match parts.len() {
+                1 => format!("/{}", relative),
+                _ => relative.to_string(),
+            })
+    }
 }
 
 /// Get the configuration file path
@@ -438,16 +1103,51 @@ fn get_config_path(app_handle: &tauri::AppHandle) -> std::io::Result<PathBuf> {
 
 /// Save configuration to disk
 fn save_config(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    write_config(app_handle, false)
+}
+
+/// Save configuration with the open, active and last notes of each vault
+/// stored by stable ID, so they are found again after being renamed or moved
+/// outside the app. Looking up IDs reads the notes, so this only runs when
+/// the app exits.
+fn save_session(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    write_config(app_handle, true)
+}
+
+fn write_config(app_handle: &tauri::AppHandle, by_id: bool) -> Result<(), Box<dyn std::error::Error>> {
     let state = app_handle.state::<AppState>();
-    let config = AppConfig {
+    let mut config = AppConfig {
         current_vault: state.current_vault.lock().unwrap().clone(),
         recent_vaults: state.recent_vaults.lock().unwrap().clone(),
         open_notes_per_vault: state.open_notes_per_vault.lock().unwrap().clone(),
         active_note_per_vault: state.active_note_per_vault.lock().unwrap().clone(),
         last_note_per_vault: state.last_note_per_vault.lock().unwrap().clone(),
         last_open_directory: state.last_open_directory.lock().unwrap().clone(),
+        periodic_notes_per_vault: state.periodic_notes_per_vault.lock().unwrap().clone(),
+        task_notifications: *state.task_notifications.lock().unwrap(),
     };
 
+    // Notes whose IDs can't be saved keep their paths
+    if by_id {
+        for (vault, notes) in config.open_notes_per_vault.iter_mut() {
+            if let Ok(ids) = note_ids::ids_for_paths(vault, notes) {
+                *notes = ids;
+            }
+        }
+        let single = config
+            .active_note_per_vault
+            .iter_mut()
+            .chain(config.last_note_per_vault.iter_mut());
+        for (vault, note) in single {
+            let id = note_ids::ids_for_paths(vault, std::slice::from_ref(note))
+                .ok()
+                .and_then(|mut ids| ids.pop());
+            if let Some(id) = id {
+                *note = id;
+            }
+        }
+    }
+
     let config_path = get_config_path(app_handle)?;
 
     // Ensure config directory exists
@@ -490,6 +1190,8 @@ pub struct AppState {
     pub active_note_per_vault: Mutex<HashMap<String, String>>,
     pub last_note_per_vault: Mutex<HashMap<String, String>>,  // Kept for migration
     pub last_open_directory: Mutex<Option<String>>,
+    pub periodic_notes_per_vault: Mutex<HashMap<String, periodic::PeriodicNotesConfig>>,
+    pub task_notifications: Mutex<bool>,
 }
 
 impl Default for AppState {
@@ -502,6 +1204,8 @@ impl Default for AppState {
             active_note_per_vault: Mutex::new(HashMap::new()),
             last_note_per_vault: Mutex::new(HashMap::new()),
             last_open_directory: Mutex::new(None),
+            periodic_notes_per_vault: Mutex::new(HashMap::new()),
+            task_notifications: Mutex::new(false),
         }
     }
 }
@@ -559,6 +1263,172 @@ fn is_hidden(name: &std::ffi::OsStr) -> bool {
     name.to_string_lossy().starts_with('.')
 }
 
+/// Collect every non-hidden file in a directory recursively, sorted by path
+fn collect_vault_files(dir: &PathBuf) -> Vec<PathBuf> {
+    fn walk(dir: &PathBuf, files: &mut Vec<PathBuf>) {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let entry_path = entry.path();
+
+                // Skip hidden files/folders
+                if entry_path.file_name().is_none_or(is_hidden) {
+                    continue;
+                }
+
+                if entry_path.is_dir() {
+                    walk(&entry_path, files);
+                } else {
+                    files.push(entry_path);
+                }
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, &mut files);
+    files.sort();
+    files
+}
+
+/// Lexically normalize a path, resolving `.` and `..` without touching the filesystem
+fn normalize_path(path: &std::path::Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            other => result.push(other.as_os_str()),
+        }
+    }
+    result
+}
+
+/// Decode `%XX` escapes in a URL path (e.g., "my%20note.md" -> "my note.md")
+fn decode_percent(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(value) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Collect every markdown note in a directory recursively, sorted by path
+fn collect_notes(dir: &PathBuf) -> Vec<PathBuf> {
+    collect_vault_files(dir)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .collect()
+}
+
+/// Parse inline `#tags` from text (e.g., "#work/project"), without the `#`
+fn parse_inline_tags(text: &str) -> Vec<String> {
+    // A tag follows whitespace or the line start and must contain a non-digit
+    let re = Regex::new(r"(?:^|\s)#([\p{L}\p{N}_/\-]+)").unwrap();
+
+    let mut tags: Vec<String> = Vec::new();
+    for cap in re.captures_iter(text) {
+        let tag = cap[1].trim_end_matches('/').to_string();
+        if tag.chars().all(|c| c.is_ascii_digit()) || tags.contains(&tag) {
+            continue;
+        }
+        tags.push(tag);
+    }
+    tags
+}
+
+/// Tags of a note: frontmatter `tags` plus inline `#tags` outside code blocks
+fn note_tags(content: &str) -> Vec<String> {
+    let (mut tags, body) = match frontmatter::parse_frontmatter(content) {
+        Some(fm) => (
+            fm.get("tags")
+                .map(|v| v.as_list())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|t| t.trim_start_matches('#').to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            &content[fm.body_start..],
+        ),
+        None => (Vec::new(), content),
+    };
+
+    let in_code = fenced_line_mask(body);
+    for (i, line) in body.lines().enumerate() {
+        if in_code.get(i).copied().unwrap_or(false) {
+            continue;
+        }
+        for tag in parse_inline_tags(line) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Mark which lines of `content` belong to fenced code blocks (``` or ~~~),
+/// fence lines included
+fn fenced_line_mask(content: &str) -> Vec<bool> {
+    let mut mask = Vec::new();
+    let mut open_fence: Option<(char, usize)> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+
+        match (open_fence, fence_char) {
+            (None, Some(c)) if fence_len >= 3 => {
+                open_fence = Some((c, fence_len));
+                mask.push(true);
+            }
+            (Some((c, len)), Some(fc))
+                if fc == c && fence_len >= len && trimmed[fence_len..].trim().is_empty() =>
+            {
+                open_fence = None;
+                mask.push(true);
+            }
+            (Some(_), _) => mask.push(true),
+            _ => mask.push(false),
+        }
+    }
+    mask
+}
+
+/// Write a file by writing a sibling temp file and renaming it into place, so
+/// a crash never leaves a half-written note
+fn write_file_atomic(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })
+}
+
 /// Scan a directory recursively and return tree nodes
 fn scan_directory_recursive(dir: &PathBuf, vault_root: &PathBuf) -> Vec<FileTreeNode> {
     let mut children = Vec::new();
@@ -1182,8 +2052,21 @@ fn create_folder(vault_path: String, folder_path: String) -> FsResult<String> {
         return FsResult::err("Folder already exists");
     }
 
+    let vault = PathBuf::from(&vault_path);
+    let created = history::missing_folders(&vault, &full_path);
+
     match fs::create_dir_all(&full_path) {
-        Ok(()) => FsResult::ok(full_path.to_string_lossy().to_string()),
+        Ok(()) => {
+            let mut operation = history::Operation::new(
+                history::OperationKind::CreateFolder,
+                format!("Create folder \"{}\"", folder_path),
+            );
+            operation.folders = created;
+            if let Err(e) = history::record(&vault, operation) {
+                return FsResult::err(&format!("Created, but failed to save history: {}", e));
+            }
+            FsResult::ok(full_path.to_string_lossy().to_string())
+        }
         Err(e) => FsResult::err(&format!("Failed to create folder: {}", e)),
     }
 }
@@ -1237,6 +2120,10 @@ fn write_note(vault_path: String, name: String, content: String, folder: Option<
 
     let path = target_dir.join(format!("{}.md", name));
 
+    let vault = PathBuf::from(&vault_path);
+    let previous = fs::read_to_string(&path).ok();
+    let created = history::missing_folders(&vault, &target_dir);
+
     // Ensure target directory exists
     if !target_dir.exists() {
         if let Err(e) = fs::create_dir_all(&target_dir) {
@@ -1244,8 +2131,21 @@ fn write_note(vault_path: String, name: String, content: String, folder: Option<
         }
     }
 
-    match fs::write(&path, &content) {
+    match write_file_atomic(&path, &content) {
         Ok(()) => {
+            if previous.as_deref() != Some(content.as_str()) {
+                let label = match previous {
+                    Some(_) => format!("Edit \"{}\"", name),
+                    None => format!("Create \"{}\"", name),
+                };
+                let mut operation = history::Operation::new(history::OperationKind::WriteNote, label);
+                operation.change(&vault, &path, previous, Some(content.clone()));
+                operation.folders = created;
+                if let Err(e) = history::record(&vault, operation) {
+                    return FsResult::err(&format!("Written, but failed to save history: {}", e));
+                }
+            }
+
             let modified = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map(|d| d.as_secs())
@@ -1263,31 +2163,57 @@ fn write_note(vault_path: String, name: String, content: String, folder: Option<
 }
 
 /// Delete a note
+///
+/// The note's content is kept in the operation history of `vault_path` (or
+/// of the vault found above the note) so the deletion can be undone.
 #[tauri::command]
-fn delete_note(path: String) -> FsResult<()> {
+fn delete_note(path: String, vault_path: Option<String>) -> FsResult<()> {
     let path_buf = PathBuf::from(&path);
 
     if !path_buf.exists() {
         return FsResult::err("Note not found");
     }
 
+    // Undo restores the note from its text, so keep a note that can't be read
+    let vault = history::vault_for(vault_path.as_deref(), &path_buf);
+    let content = match &vault {
+        Some(_) => match fs::read_to_string(&path_buf) {
+            Ok(content) => Some(content),
+            Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+        },
+        None => None,
+    };
+
     match fs::remove_file(&path_buf) {
-        Ok(()) => FsResult::ok(()),
+        Ok(()) => {
+            if let Some(vault) = vault {
+                let name = path_buf.file_stem().unwrap_or_default().to_string_lossy();
+                let mut operation = history::Operation::new(
+                    history::OperationKind::DeleteNote,
+                    format!("Delete \"{}\"", name),
+                );
+                operation.change(&vault, &path_buf, content, None);
+                if let Err(e) = history::record(&vault, operation) {
+                    return FsResult::err(&format!("Deleted, but failed to save history: {}", e));
+                }
+            }
+            FsResult::ok(())
+        }
         Err(e) => FsResult::err(&format!("Failed to delete note: {}", e)),
     }
 }
 
 /// Rename a note
 #[tauri::command]
-fn rename_note(path: String, new_name: String) -> FsResult<Note> {
+fn rename_note(path: String, new_name: String, vault_path: Option<String>) -> FsResult<Note> {
     let old_path = PathBuf::from(&path);
 
     if !old_path.exists() {
         return FsResult::err("Note not found");
     }
 
-    let vault_path = old_path.parent().unwrap_or(&old_path);
-    let new_path = vault_path.join(format!("{}.md", new_name));
+    let folder = old_path.parent().unwrap_or(&old_path);
+    let new_path = folder.join(format!("{}.md", new_name));
 
     if new_path.exists() {
         return FsResult::err("A note with this name already exists");
@@ -1295,7 +2221,24 @@ fn rename_note(path: String, new_name: String) -> FsResult<Note> {
 
     match fs::rename(&old_path, &new_path) {
         Ok(()) => {
+            note_ids::record_move(&old_path, &new_path);
             let content = fs::read_to_string(&new_path).unwrap_or_default();
+            if let Some(vault) = history::vault_for(vault_path.as_deref(), &old_path) {
+                let old_name = old_path.file_stem().unwrap_or_default().to_string_lossy();
+                let mut operation = history::Operation::new(
+                    history::OperationKind::RenameNote,
+                    format!("Rename \"{}\" to \"{}\"", old_name, new_name),
+                );
+                operation.change(&vault, &old_path, Some(content.clone()), None);
+                operation.change(&vault, &new_path, None, Some(content.clone()));
+                operation.moves.push((
+                    history::relative_path(&vault, &old_path),
+                    history::relative_path(&vault, &new_path),
+                ));
+                if let Err(e) = history::record(&vault, operation) {
+                    return FsResult::err(&format!("Renamed, but failed to save history: {}", e));
+                }
+            }
             let modified = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map(|d| d.as_secs())
@@ -1465,6 +2408,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState::default())
         .setup(|app| {
             // Load configuration from disk
@@ -1488,18 +2432,39 @@ pub fn run() {
             if let Ok(mut recent) = state.recent_vaults.lock() {
                 *recent = config.recent_vaults;
             }
+            // Notes are stored by ID (or path); keep their current paths
             if let Ok(mut open_notes) = state.open_notes_per_vault.lock() {
-                *open_notes = config.open_notes_per_vault;
+                *open_notes = config
+                    .open_notes_per_vault
+                    .into_iter()
+                    .map(|(vault, ids)| {
+                        let paths = note_ids::paths_for_ids(&vault, &ids);
+                        (vault, paths)
+                    })
+                    .collect();
             }
+            let current_path = |(vault, id): (String, String)| {
+                let path = note_ids::paths_for_ids(&vault, &[id]).pop()?;
+                Some((vault, path))
+            };
             if let Ok(mut active_notes) = state.active_note_per_vault.lock() {
-                *active_notes = config.active_note_per_vault;
+                *active_notes = config.active_note_per_vault.into_iter().filter_map(current_path).collect();
             }
             if let Ok(mut last_notes) = state.last_note_per_vault.lock() {
-                *last_notes = config.last_note_per_vault;
+                *last_notes = config.last_note_per_vault.into_iter().filter_map(current_path).collect();
             }
             if let Ok(mut last_dir) = state.last_open_directory.lock() {
                 *last_dir = config.last_open_directory;
             }
+            if let Ok(mut periodic) = state.periodic_notes_per_vault.lock() {
+                *periodic = config.periodic_notes_per_vault;
+            }
+            if let Ok(mut notifications) = state.task_notifications.lock() {
+                *notifications = config.task_notifications;
+            }
+
+            // Emit `task-due` events in the background
+            reminders::start_task_scheduler(app.handle().clone());
 
             Ok(())
         })
@@ -1539,16 +2504,75 @@ pub fn run() {
             write_note,
             delete_note,
             rename_note,
+            note_ids::get_note_id,
+            note_ids::find_note_by_id,
             // Wiki links
             parse_links,
             get_backlinks,
             resolve_wiki_link,
+            resolve_link_candidates,
+            headings::resolve_link_location,
+            headings::rename_heading,
+            outline::get_outline,
+            outline::update_toc,
+            blocks::generate_block_id,
+            embeds::resolve_embeds,
+            links::find_unresolved_links,
+            links::fix_unresolved_link,
+            links::convert_link_style,
+            duplicates::get_duplicate_names,
+            duplicates::qualify_ambiguous_links,
+            similarity::find_duplicate_notes,
+            similarity::merge_duplicate_notes,
+            aliases::get_alias_collisions,
+            aliases::get_quick_open_items,
+            mentions::get_unlinked_mentions,
+            mentions::link_unlinked_mentions,
+            // Graph
+            graph::get_graph,
+            graph::get_graph_analytics,
+            graph::find_shortest_path,
+            // Rendering
+            markdown::render_markdown,
+            // Find and replace
+            find_replace::find_replace,
+            // Operation history
+            history::undo_operation,
+            history::undo_last_operation,
+            history::redo_operation,
+            history::get_operation_history,
+            // Refactoring
+            refactor::split_note,
+            refactor::extract_selection,
+            refactor::merge_notes,
+            // Attachments
+            attachments::get_attachment_report,
+            attachments::trash_unused_attachments,
+            // Templates
+            templates::list_templates,
+            templates::create_note_from_template,
+            // Periodic notes
+            periodic::get_periodic_notes_config,
+            periodic::set_periodic_notes_config,
+            periodic::open_or_create_periodic_note,
+            periodic::get_adjacent_periodic_note,
+            periodic::get_periodic_note_calendar,
+            // Tasks
+            tasks::query_tasks,
+            tasks::toggle_task,
+            reminders::get_task_notifications,
+            reminders::set_task_notifications,
             // Theme management
             list_custom_themes,
             import_theme,
             export_theme,
             delete_custom_theme
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                let _ = save_session(app_handle);
+            }
+        });
 }
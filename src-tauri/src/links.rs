@@ -0,0 +1,588 @@
+//! Vault-wide link maintenance: broken link reports and bulk link rewrites
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::attachments::looks_like_attachment;
+use crate::blocks::{find_block, parse_blocks, Block};
+use crate::headings::{find_heading, heading_slug, parse_headings, Heading};
+use crate::history::Journal;
+use crate::{parse_note_links, parse_wiki_links, split_link_anchor, FsResult, NoteIndex, WikiLink};
+
+/// Minimum similarity (0-1) for a name to be suggested as a fix
+const SUGGESTION_THRESHOLD: f64 = 0.5;
+
+/// Maximum number of suggested fixes per broken target
+const MAX_SUGGESTIONS: usize = 5;
+
+/// What part of a broken link could not be found
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnresolvedKind {
+    /// No note matches the target
+    Note,
+    /// The note exists but has no matching `#heading`
+    Heading,
+    /// The note exists but has no matching `#^block-id`
+    Block,
+}
+
+/// One occurrence of a broken link
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnresolvedOccurrence {
+    pub source_path: String,
+    pub source_name: String,
+    /// Line number of the link (1-based)
+    pub line: usize,
+    pub link: WikiLink,
+}
+
+/// A suggested replacement for a broken link target
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkSuggestion {
+    /// Link target to use instead (e.g., "Meeting notes" or "note#Heading")
+    pub replacement: String,
+    /// Path of the note the replacement points to
+    pub path: String,
+    /// Name similarity (0-1)
+    pub score: f64,
+}
+
+/// All occurrences of one broken target
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnresolvedLinkGroup {
+    pub kind: UnresolvedKind,
+    /// The target as first written
+    pub target: String,
+    /// The missing heading, for `heading` groups
+    pub heading: Option<String>,
+    /// The missing block ID, for `block` groups
+    pub block_id: Option<String>,
+    /// The note the target resolves to, for `heading` and `block` groups
+    pub note_path: Option<String>,
+    pub occurrences: Vec<UnresolvedOccurrence>,
+    pub suggestions: Vec<LinkSuggestion>,
+}
+
+/// Notes and links touched by a bulk link rewrite
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct LinkRewrite {
+    pub changed_notes: Vec<String>,
+    pub links_updated: usize,
+    /// The recorded operation, for `undo_operation` (not set when nothing
+    /// changed)
+    pub operation_id: Option<String>,
+}
+
+/// Link syntax for `convert_link_style`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// `[[note#heading|text]]`
+    Wiki,
+    /// `[text](folder/note.md#heading)`
+    Markdown,
+}
+
+/// Line number (1-based) of a byte offset
+pub(crate) fn line_at(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Format a wiki link from its parts; `anchor` is a heading or `^block-id`
+pub(crate) fn format_wiki_link(
+    target: &str,
+    anchor: Option<&str>,
+    display: Option<&str>,
+) -> String {
+    let mut link = format!("[[{}", target);
+    if let Some(anchor) = anchor {
+        link.push('#');
+        link.push_str(anchor);
+    }
+    if let Some(display) = display {
+        link.push('|');
+        link.push_str(display);
+    }
+    link.push_str("]]");
+    link
+}
+
+/// Replace wiki links in `content`; `replace` returns the new link text for
+/// links that should change, and embeds keep their leading `!`. Returns the
+/// new content and the number of links replaced.
+pub(crate) fn rewrite_wiki_links<F>(content: &str, replace: F) -> (String, usize)
+where
+    F: FnMut(&WikiLink) -> Option<String>,
+{
+    rewrite_links(content, parse_wiki_links(content), replace)
+}
+
+/// Replace the given links (in document order) in `content`, as for
+/// `rewrite_wiki_links`
+pub(crate) fn rewrite_links<F>(
+    content: &str,
+    links: Vec<WikiLink>,
+    mut replace: F,
+) -> (String, usize)
+where
+    F: FnMut(&WikiLink) -> Option<String>,
+{
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut count = 0;
+
+    for link in links {
+        if let Some(new_link) = replace(&link) {
+            result.push_str(&content[last..link.start]);
+            if link.is_embed && !new_link.starts_with('!') {
+                result.push('!');
+            }
+            result.push_str(&new_link);
+            last = link.end;
+            count += 1;
+        }
+    }
+
+    result.push_str(&content[last..]);
+    (result, count)
+}
+
+/// Headings and blocks of indexed notes, read on first use
+struct AnchorCache<'a> {
+    index: &'a NoteIndex,
+    anchors: HashMap<usize, (Vec<Heading>, Vec<Block>)>,
+}
+
+impl<'a> AnchorCache<'a> {
+    fn new(index: &'a NoteIndex) -> Self {
+        AnchorCache {
+            index,
+            anchors: HashMap::new(),
+        }
+    }
+
+    fn load(&mut self, note: usize) -> &(Vec<Heading>, Vec<Block>) {
+        let index = self.index;
+        self.anchors.entry(note).or_insert_with(|| {
+            fs::read_to_string(&index.notes[note])
+                .map(|content| (parse_headings(&content), parse_blocks(&content)))
+                .unwrap_or_default()
+        })
+    }
+
+    fn headings(&mut self, note: usize) -> &[Heading] {
+        &self.load(note).0
+    }
+
+    fn blocks(&mut self, note: usize) -> &[Block] {
+        &self.load(note).1
+    }
+}
+
+/// Check a link against the index: `None` when it resolves, otherwise what is
+/// missing and the note the target resolved to (for missing headings)
+fn check_link(
+    link: &WikiLink,
+    source: &Path,
+    index: &NoteIndex,
+    anchors: &mut AnchorCache,
+) -> Option<(UnresolvedKind, Option<usize>)> {
+    let note = match index.resolve_link(link, source) {
+        Some(note) => note,
+        None => return Some((UnresolvedKind::Note, None)),
+    };
+
+    if let Some(heading) = &link.heading {
+        if find_heading(anchors.headings(note), heading).is_none() {
+            return Some((UnresolvedKind::Heading, Some(note)));
+        }
+    }
+    if let Some(block_id) = &link.block_id {
+        if find_block(anchors.blocks(note), block_id).is_none() {
+            return Some((UnresolvedKind::Block, Some(note)));
+        }
+    }
+    None
+}
+
+/// Similarity of two names (0-1), case-insensitive
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    let edit = strsim::normalized_damerau_levenshtein(&a, &b);
+
+    // "meeting" should still suggest "Meeting notes 2024"
+    let contained = if !a.is_empty() && !b.is_empty() && (a.contains(&b) || b.contains(&a)) {
+        0.6 + 0.4 * a.len().min(b.len()) as f64 / a.len().max(b.len()) as f64
+    } else {
+        0.0
+    };
+
+    edit.max(contained)
+}
+
+/// Rank candidates by similarity, keeping the best few above the threshold
+fn top_suggestions(mut suggestions: Vec<LinkSuggestion>) -> Vec<LinkSuggestion> {
+    suggestions.retain(|s| s.score >= SUGGESTION_THRESHOLD);
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.replacement.cmp(&b.replacement))
+    });
+    let mut seen = HashSet::new();
+    suggestions.retain(|s| seen.insert(s.replacement.to_lowercase()));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// Suggest existing notes for a missing target
+fn suggest_notes(target: &str, index: &NoteIndex) -> Vec<LinkSuggestion> {
+    let wanted = target.rsplit('/').next().unwrap_or(target);
+
+    let suggestions = (0..index.notes.len())
+        .map(|i| {
+            let name = index.name(i);
+            LinkSuggestion {
+                score: name_similarity(wanted, &name),
+                replacement: index.link_target(i),
+                path: index.notes[i].to_string_lossy().to_string(),
+            }
+        })
+        .collect();
+
+    top_suggestions(suggestions)
+}
+
+/// Suggest existing headings of the target note for a missing heading
+fn suggest_headings(
+    target: &str,
+    heading: &str,
+    note: usize,
+    index: &NoteIndex,
+    anchors: &mut AnchorCache,
+) -> Vec<LinkSuggestion> {
+    let wanted = heading.rsplit('#').next().unwrap_or(heading);
+    let path = index.notes[note].to_string_lossy().to_string();
+
+    let suggestions = anchors
+        .headings(note)
+        .iter()
+        .map(|h| LinkSuggestion {
+            replacement: format!("{}#{}", target, h.text),
+            path: path.clone(),
+            score: name_similarity(&heading_slug(wanted), &heading_slug(&h.text)),
+        })
+        .collect();
+
+    top_suggestions(suggestions)
+}
+
+/// List every wiki link whose note, `#heading` or `#^block` does not exist,
+/// grouped by target, with suggested fixes - Tauri command
+#[tauri::command]
+pub fn find_unresolved_links(vault_path: String) -> FsResult<Vec<UnresolvedLinkGroup>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut anchors = AnchorCache::new(&index);
+    let mut groups: Vec<UnresolvedLinkGroup> = Vec::new();
+    let mut group_keys: HashMap<String, usize> = HashMap::new();
+
+    for (i, path) in index.notes.iter().enumerate() {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        for link in parse_wiki_links(&content) {
+            // Links to files are covered by the attachment report
+            if looks_like_attachment(&link.target) {
+                continue;
+            }
+
+            let (kind, note) = match check_link(&link, path, &index, &mut anchors) {
+                Some(missing) => missing,
+                None => continue,
+            };
+
+            let key = match kind {
+                UnresolvedKind::Note => link.target.to_lowercase(),
+                UnresolvedKind::Heading => format!(
+                    "{}#{}",
+                    link.target.to_lowercase(),
+                    heading_slug(link.heading.as_deref().unwrap_or_default())
+                ),
+                UnresolvedKind::Block => format!(
+                    "{}#^{}",
+                    link.target.to_lowercase(),
+                    link.block_id.as_deref().unwrap_or_default().to_lowercase()
+                ),
+            };
+
+            let group = *group_keys.entry(key).or_insert_with(|| {
+                groups.push(UnresolvedLinkGroup {
+                    kind,
+                    target: link.target.clone(),
+                    heading: match kind {
+                        UnresolvedKind::Heading => link.heading.clone(),
+                        _ => None,
+                    },
+                    block_id: match kind {
+                        UnresolvedKind::Block => link.block_id.clone(),
+                        _ => None,
+                    },
+                    note_path: note.map(|n| index.notes[n].to_string_lossy().to_string()),
+                    occurrences: Vec::new(),
+                    suggestions: Vec::new(),
+                });
+                groups.len() - 1
+            });
+
+            groups[group].occurrences.push(UnresolvedOccurrence {
+                source_path: path.to_string_lossy().to_string(),
+                source_name: index.name(i),
+                line: line_at(&content, link.start),
+                link,
+            });
+        }
+    }
+
+    for group in &mut groups {
+        let note = group
+            .note_path
+            .as_ref()
+            .and_then(|p| index.position(&PathBuf::from(p)));
+        group.suggestions = match (group.kind, &group.heading, note) {
+            (UnresolvedKind::Heading, Some(heading), Some(note)) => {
+                suggest_headings(&group.target, heading, note, &index, &mut anchors)
+            }
+            // Block IDs are arbitrary, so there is nothing similar to suggest
+            (UnresolvedKind::Block, _, _) => Vec::new(),
+            _ => suggest_notes(&group.target, &index),
+        };
+    }
+
+    groups.sort_by(|a, b| {
+        b.occurrences
+            .len()
+            .cmp(&a.occurrences.len())
+            .then_with(|| a.target.to_lowercase().cmp(&b.target.to_lowercase()))
+    });
+
+    FsResult::ok(groups)
+}
+
+/// Replace every still-broken link to `target` (and `heading` or
+/// `block_id`, if given) with `replacement` across the vault - Tauri command
+///
+/// A replacement without `#` only swaps the note, keeping each link's
+/// heading or block; one with `#` sets both. Display text is always kept.
+#[tauri::command]
+pub fn fix_unresolved_link(
+    vault_path: String,
+    target: String,
+    heading: Option<String>,
+    block_id: Option<String>,
+    replacement: String,
+) -> FsResult<LinkRewrite> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let replacement = replacement.trim();
+    if replacement.is_empty() {
+        return FsResult::err("Replacement cannot be empty");
+    }
+    let (new_target, new_heading) = match split_link_anchor(replacement) {
+        (t, Some(h)) => (t.trim(), Some(h.trim())),
+        (t, None) => (t, None),
+    };
+
+    let target = target.trim().to_lowercase();
+    let heading_slug_wanted = heading.as_deref().map(heading_slug);
+
+    let index = NoteIndex::build(&vault);
+    let mut anchors = AnchorCache::new(&index);
+    let mut summary = LinkRewrite::default();
+    let label = format!("Replace links to \"{}\" with \"{}\"", target, replacement);
+    let mut journal = Journal::new(Some(vault.clone()), label);
+
+    for path in &index.notes {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        let (updated, count) = rewrite_wiki_links(&content, |link| {
+            if link.target.to_lowercase() != target {
+                return None;
+            }
+            if let Some(wanted) = &heading_slug_wanted {
+                if link.heading.as_deref().map(heading_slug).as_ref() != Some(wanted) {
+                    return None;
+                }
+            }
+            if let Some(wanted) = &block_id {
+                let id = wanted.trim_start_matches('^');
+                if !link
+                    .block_id
+                    .as_deref()
+                    .is_some_and(|b| b.eq_ignore_ascii_case(id))
+                {
+                    return None;
+                }
+            }
+            // Leave links that have been fixed since the report was shown
+            check_link(link, path, &index, &mut anchors)?;
+
+            let anchor = link.anchor();
+            Some(format_wiki_link(
+                new_target,
+                new_heading.or(anchor.as_deref()),
+                link.display_text.as_deref(),
+            ))
+        });
+
+        if count == 0 {
+            continue;
+        }
+
+        if let Err(e) = journal.write(path, &content, &updated) {
+            return FsResult::err(&format!("Failed to update {}: {}", path.display(), e));
+        }
+        summary
+            .changed_notes
+            .push(path.to_string_lossy().to_string());
+        summary.links_updated += count;
+    }
+
+    match journal.finish() {
+        Ok(id) => summary.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Updated, but failed to save history: {}", e)),
+    }
+    FsResult::ok(summary)
+}
+
+/// Path from a folder to a file, with `/` separators (e.g., "../b/note.md")
+pub(crate) fn relative_link_path(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+/// Percent-encode the characters that would end or confuse a Markdown link URL
+pub(crate) fn encode_link_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' | '%' | '(' | ')' | '[' | ']' | '<' | '>' | '#' | '?' | '"' | '\\' => {
+                encoded.push_str(&format!("%{:02X}", c as u32))
+            }
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Rewrite every resolvable link in the vault to one style - Tauri command
+///
+/// Wiki links become Markdown links with a path relative to the linking note;
+/// Markdown links become wiki links using the shortest unambiguous target.
+/// Links that do not resolve to a note are left unchanged.
+#[tauri::command]
+pub fn convert_link_style(vault_path: String, to: LinkStyle) -> FsResult<LinkRewrite> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut summary = LinkRewrite::default();
+    let label = match to {
+        LinkStyle::Wiki => "Convert links to wiki links",
+        LinkStyle::Markdown => "Convert links to Markdown links",
+    };
+    let mut journal = Journal::new(Some(vault.clone()), label);
+
+    for path in &index.notes {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        let note_dir = path.parent().unwrap_or(&vault);
+
+        let (updated, count) = rewrite_links(&content, parse_note_links(&content), |link| {
+            if link.is_markdown == (to == LinkStyle::Markdown) {
+                return None;
+            }
+            let note = index.resolve_link(link, path)?;
+            let anchor = link.anchor();
+
+            match to {
+                LinkStyle::Markdown => {
+                    let mut url =
+                        encode_link_path(&relative_link_path(note_dir, &index.notes[note]));
+                    if let Some(anchor) = &anchor {
+                        url.push('#');
+                        url.push_str(&encode_link_path(anchor));
+                    }
+                    let text = link
+                        .display_text
+                        .clone()
+                        .unwrap_or_else(|| match &link.heading {
+                            Some(heading) => format!("{} > {}", link.target, heading),
+                            None => link.target.clone(),
+                        });
+                    let bang = if link.is_embed { "!" } else { "" };
+                    Some(format!("{}[{}]({})", bang, text, url))
+                }
+                LinkStyle::Wiki => {
+                    let target = index.link_target(note);
+                    // Drop display text that just repeats the note name (and heading)
+                    let name = index.name(note);
+                    let display = link.display_text.as_deref().filter(|d| {
+                        let suffix = link.heading.as_ref().map(|h| format!(" > {}", h));
+                        let d = suffix.and_then(|s| d.strip_suffix(&s)).unwrap_or(d);
+                        d != target && d != name
+                    });
+                    Some(format_wiki_link(&target, anchor.as_deref(), display))
+                }
+            }
+        });
+
+        if count == 0 {
+            continue;
+        }
+
+        if let Err(e) = journal.write(path, &content, &updated) {
+            return FsResult::err(&format!("Failed to update {}: {}", path.display(), e));
+        }
+        summary
+            .changed_notes
+            .push(path.to_string_lossy().to_string());
+        summary.links_updated += count;
+    }
+
+    match journal.finish() {
+        Ok(id) => summary.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Updated, but failed to save history: {}", e)),
+    }
+    FsResult::ok(summary)
+}
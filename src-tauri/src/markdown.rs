@@ -0,0 +1,667 @@
+//! Markdown to sanitized HTML for the preview, with highlighted code blocks,
+//! wiki links rendered as anchors, math as MathML and diagrams as SVG
+
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use syntect::highlighting::{
+    Color, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
+};
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::attachments::{index_by_file_name, looks_like_attachment, resolve_attachment};
+use crate::diagrams::{is_diagram_language, render_diagram};
+use crate::links::line_at;
+use crate::math::latex_to_mathml;
+use crate::{
+    collect_vault_files, frontmatter, parse_wiki_links, FsResult, NoteIndex, ThemeSyntaxColors,
+    WikiLink,
+};
+
+/// Prefix of the CSS classes on highlighted code
+const CLASS_PREFIX: &str = "hl-";
+
+/// Options for `render_markdown`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RenderOptions {
+    /// Vault used to resolve wiki links; without it every link is unresolved
+    #[serde(default)]
+    pub vault_path: Option<String>,
+    /// The note being rendered, so links resolve relative to it
+    #[serde(default)]
+    pub source_path: Option<String>,
+    /// Syntax colors of the active theme, used for the code stylesheet
+    #[serde(default)]
+    pub syntax_colors: Option<ThemeSyntaxColors>,
+}
+
+/// Rendered note
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedMarkdown {
+    pub html: String,
+    /// Stylesheet for highlighted code (empty without `syntax_colors`)
+    pub css: String,
+    /// Math and diagrams that could not be rendered
+    pub problems: Vec<RenderProblem>,
+}
+
+/// What kind of block failed to render
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderProblemKind {
+    Math,
+    Diagram,
+}
+
+/// A formula or diagram left as source because it could not be rendered
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RenderProblem {
+    pub kind: RenderProblemKind,
+    /// Line in the note (1-based, counting frontmatter)
+    pub line: usize,
+    /// Column in that line (1-based, in characters)
+    pub column: usize,
+    pub message: String,
+}
+
+/// Where a byte offset in the note falls, as a `(line, column)` pair
+fn position(content: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(content.len());
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    (
+        line_at(content, offset),
+        content[line_start..offset].chars().count() + 1,
+    )
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Parse a `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(...)` or `rgba(...)` color
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    let hex = match value.strip_prefix('#') {
+        Some(hex) => hex,
+        None => return parse_rgb_function(value),
+    };
+    let digits: Vec<u8> = match hex.len() {
+        3 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some(Color {
+        r: digits[0],
+        g: digits[1],
+        b: digits[2],
+        a: digits.get(3).copied().unwrap_or(0xff),
+    })
+}
+
+/// Parse `rgb(r, g, b)` or `rgba(r, g, b, a)`, also in the space-separated
+/// `rgb(r g b / a)` form; channels may be numbers or percentages
+fn parse_rgb_function(value: &str) -> Option<Color> {
+    let lower = value.to_ascii_lowercase();
+    let args = lower
+        .strip_prefix("rgba(")
+        .or_else(|| lower.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let parts: Vec<&str> = args
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return None;
+    }
+
+    // Scale a channel to 0..=255, where `max` is the value of a bare number
+    // that means full intensity
+    let channel = |part: &str, max: f32| -> Option<u8> {
+        let fraction = match part.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+            None => part.parse::<f32>().ok()? / max,
+        };
+        Some((fraction.clamp(0.0, 1.0) * 255.0).round() as u8)
+    };
+
+    Some(Color {
+        r: channel(parts[0], 255.0)?,
+        g: channel(parts[1], 255.0)?,
+        b: channel(parts[2], 255.0)?,
+        a: match parts.get(3) {
+            Some(alpha) => channel(alpha, 1.0)?,
+            None => 0xff,
+        },
+    })
+}
+
+/// A highlighting theme built from the app theme's syntax colors.
+///
+/// The app theme only has Markdown colors, so code scopes borrow them:
+/// keywords use `heading1`, strings `heading2`, constants `heading3`,
+/// types and parameters `heading4`, names `link` and comments `quote`.
+fn code_theme(colors: &ThemeSyntaxColors) -> Theme {
+    let scopes = [
+        ("comment", &colors.quote),
+        ("keyword, storage", &colors.heading1),
+        ("string", &colors.heading2),
+        ("constant", &colors.heading3),
+        (
+            "entity.name.type, support.type, variable.parameter",
+            &colors.heading4,
+        ),
+        (
+            "entity.name, support.function, meta.function-call",
+            &colors.link,
+        ),
+    ];
+
+    Theme {
+        name: Some("Open Note".to_string()),
+        settings: ThemeSettings {
+            foreground: parse_color(&colors.code),
+            background: parse_color(&colors.code_bg),
+            ..ThemeSettings::default()
+        },
+        scopes: scopes
+            .iter()
+            .filter_map(|(selector, color)| {
+                Some(ThemeItem {
+                    scope: selector.parse::<ScopeSelectors>().ok()?,
+                    style: StyleModifier {
+                        foreground: Some(parse_color(color)?),
+                        ..StyleModifier::default()
+                    },
+                })
+            })
+            .collect(),
+        ..Theme::default()
+    }
+}
+
+/// HTML for a fenced or indented code block, highlighted when the language
+/// is known
+fn render_code_block(lang: &str, code: &str) -> String {
+    let syntaxes = syntax_set();
+    let class = match lang {
+        "" => String::new(),
+        lang => format!(" class=\"language-{}\"", escape_html(lang)),
+    };
+
+    let highlighted = syntaxes.find_syntax_by_token(lang).and_then(|syntax| {
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            syntaxes,
+            ClassStyle::SpacedPrefixed {
+                prefix: CLASS_PREFIX,
+            },
+        );
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(generator.finalize())
+    });
+
+    match highlighted {
+        Some(body) => format!(
+            "<pre class=\"{}code\"><code{}>{}</code></pre>\n",
+            CLASS_PREFIX, class, body
+        ),
+        None => format!("<pre><code{}>{}</code></pre>\n", class, escape_html(code)),
+    }
+}
+
+/// Resolves wiki links for rendering
+struct LinkResolver {
+    index: Option<NoteIndex>,
+    source: Option<PathBuf>,
+    attachments: Option<HashMap<String, Vec<PathBuf>>>,
+}
+
+impl LinkResolver {
+    fn new(options: &RenderOptions, needed: bool) -> LinkResolver {
+        let vault = options
+            .vault_path
+            .as_ref()
+            .filter(|_| needed)
+            .map(PathBuf::from);
+        LinkResolver {
+            index: vault.as_ref().map(NoteIndex::build),
+            source: options.source_path.as_ref().map(PathBuf::from),
+            attachments: vault.map(|v| index_by_file_name(&collect_vault_files(&v))),
+        }
+    }
+
+    /// The file a link leads to, if it exists
+    fn resolve(&self, link: &WikiLink) -> Option<PathBuf> {
+        let index = self.index.as_ref()?;
+        if looks_like_attachment(&link.target) {
+            let note_dir = self
+                .source
+                .as_deref()
+                .and_then(Path::parent)
+                .unwrap_or(&index.vault);
+            return resolve_attachment(
+                &index.vault,
+                note_dir,
+                &link.target,
+                self.attachments.as_ref()?,
+            );
+        }
+        let source = self.source.clone().unwrap_or_else(|| index.vault.clone());
+        index
+            .resolve_link(link, &source)
+            .map(|i| index.notes[i].clone())
+    }
+
+    /// An anchor for a wiki link or embed, with its resolved state, target
+    /// and file path as `data-` attributes
+    fn anchor(&self, link: &WikiLink) -> String {
+        let path = self.resolve(link);
+        let mut classes = vec![
+            "wiki-link",
+            if path.is_some() {
+                "resolved"
+            } else {
+                "unresolved"
+            },
+        ];
+        if link.is_embed {
+            classes.push("internal-embed");
+        }
+
+        let text = match (&link.display_text, &link.heading) {
+            (Some(display), _) => display.clone(),
+            (None, Some(heading)) if !link.target.is_empty() => {
+                format!("{} > {}", link.target, heading)
+            }
+            (None, Some(heading)) => heading.clone(),
+            (None, None) => link.target.clone(),
+        };
+
+        let mut attributes = format!(
+            "class=\"{}\" href=\"#\" data-target=\"{}\"",
+            classes.join(" "),
+            escape_html(&link.target)
+        );
+        if let Some(anchor) = link.anchor() {
+            attributes.push_str(&format!(" data-anchor=\"{}\"", escape_html(&anchor)));
+        }
+        if let Some(path) = path {
+            attributes.push_str(&format!(
+                " data-path=\"{}\"",
+                escape_html(&path.to_string_lossy())
+            ));
+        }
+        format!("<a {}>{}</a>", attributes, escape_html(&text))
+    }
+}
+
+/// Placeholder for the `i`th wiki link, replaced with its anchor after
+/// rendering (private-use characters never appear in Markdown syntax)
+fn placeholder(i: usize) -> String {
+    format!("\u{E000}{}\u{E001}", i)
+}
+
+/// Split text into text and links for bare URLs (`https://...`, `www...`)
+fn autolink<'a>(text: CowStr<'a>, events: &mut Vec<Event<'a>>) {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let re = URL
+        .get_or_init(|| Regex::new(r#"(?:https?://|www\.)[^\s<>"]*[^\s<>".,:;!?'")\]]"#).unwrap());
+
+    let mut cursor = 0;
+    for m in re.find_iter(&text) {
+        let before = text[..m.start()].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric()) {
+            continue;
+        }
+        if m.start() > cursor {
+            events.push(Event::Text(text[cursor..m.start()].to_string().into()));
+        }
+        let url = match m.as_str() {
+            url if url.starts_with("www.") => format!("https://{}", url),
+            url => url.to_string(),
+        };
+        events.push(Event::Start(Tag::Link {
+            link_type: pulldown_cmark::LinkType::Autolink,
+            dest_url: url.into(),
+            title: "".into(),
+            id: "".into(),
+        }));
+        events.push(Event::Text(m.as_str().to_string().into()));
+        events.push(Event::End(TagEnd::Link));
+        cursor = m.end();
+    }
+
+    if cursor == 0 {
+        events.push(Event::Text(text));
+    } else if cursor < text.len() {
+        events.push(Event::Text(text[cursor..].to_string().into()));
+    }
+}
+
+/// MathML elements produced by `math::latex_to_mathml`
+const MATHML_TAGS: &[&str] = &[
+    "math",
+    "semantics",
+    "annotation",
+    "mrow",
+    "mi",
+    "mn",
+    "mo",
+    "mtext",
+    "mspace",
+    "msup",
+    "msub",
+    "msubsup",
+    "munder",
+    "mover",
+    "munderover",
+    "mfrac",
+    "msqrt",
+    "mroot",
+    "mtable",
+    "mtr",
+    "mtd",
+];
+
+const MATHML_ATTRIBUTES: &[&str] = &[
+    "xmlns",
+    "display",
+    "encoding",
+    "mathvariant",
+    "stretchy",
+    "fence",
+    "largeop",
+    "movablelimits",
+    "accent",
+    "accentunder",
+    "linethickness",
+    "columnalign",
+    "minsize",
+    "maxsize",
+    "width",
+];
+
+/// SVG elements produced by layout-rs
+const SVG_TAGS: &[&str] = &[
+    "svg", "defs", "marker", "clipPath", "polygon", "rect", "path", "line", "ellipse", "circle",
+    "text", "tspan", "textPath",
+];
+
+const SVG_ATTRIBUTES: &[&str] = &[
+    "xmlns",
+    "viewBox",
+    "width",
+    "height",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "rx",
+    "ry",
+    "r",
+    "d",
+    "points",
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-dasharray",
+    "clip-path",
+    "marker-start",
+    "marker-end",
+    "markerWidth",
+    "markerHeight",
+    "refX",
+    "refY",
+    "orient",
+    "dominant-baseline",
+    "text-anchor",
+    "dy",
+    "startOffset",
+    "font-size",
+    "font-family",
+    "href",
+];
+
+/// Sanitize rendered HTML, keeping what Markdown produces (task list
+/// checkboxes, footnote IDs, table alignment), MathML and diagram SVG, plus
+/// classes and `data-` attributes
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    for tag in MATHML_TAGS {
+        builder.add_tag_attributes(tag, MATHML_ATTRIBUTES);
+    }
+    for tag in SVG_TAGS {
+        builder.add_tag_attributes(tag, SVG_ATTRIBUTES);
+    }
+
+    builder
+        .add_tags(MATHML_TAGS)
+        .add_tags(SVG_TAGS)
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .filter_style_properties(HashSet::from(["text-align"]))
+        .add_generic_attributes(["class", "id"])
+        .add_generic_attribute_prefixes(["data-"])
+        .attribute_filter(|element, attribute, value| {
+            if element == "input" && attribute == "type" && value != "checkbox" {
+                return None;
+            }
+            Some(value.into())
+        })
+        .clean(html)
+        .to_string()
+}
+
+/// Render note content to sanitized HTML - Tauri command
+///
+/// Supports GFM tables, task lists, footnotes, strikethrough and bare URL
+/// autolinks. Code blocks get `hl-` classes, styled by the returned `css`
+/// when `syntax_colors` is given. Wiki links become
+/// `<a class="wiki-link resolved|unresolved" data-target=...>` anchors.
+///
+/// `$inline$` and `$$display$$` math is rendered to MathML, and `mermaid`
+/// and `dot` code blocks to inline SVG, so the HTML needs no scripts. Blocks
+/// that fail stay as source and are listed in `problems`.
+#[tauri::command]
+pub fn render_markdown(
+    content: String,
+    options: Option<RenderOptions>,
+) -> FsResult<RenderedMarkdown> {
+    let options = options.unwrap_or_default();
+    let body_start = frontmatter::parse_frontmatter(&content).map_or(0, |fm| fm.body_start);
+    let body = &content[body_start..];
+
+    // Swap wiki links for placeholders so Markdown leaves them alone
+    let links = parse_wiki_links(body);
+    let resolver = LinkResolver::new(&options, !links.is_empty());
+    let mut source = String::with_capacity(body.len());
+    // (end of placeholder in `source`, end of link in `body`)
+    let mut shifts = Vec::with_capacity(links.len());
+    let mut cursor = 0;
+    for (i, link) in links.iter().enumerate() {
+        source.push_str(&body[cursor..link.start]);
+        source.push_str(&placeholder(i));
+        shifts.push((source.len(), link.end));
+        cursor = link.end;
+    }
+    source.push_str(&body[cursor..]);
+
+    // Position in the note of an offset in `source`
+    let note_position = |offset: usize| {
+        let body_offset = match shifts.iter().rev().find(|(end, _)| *end <= offset) {
+            Some((end, body_end)) => body_end + (offset - end),
+            None => offset,
+        };
+        position(&content, body_start + body_offset)
+    };
+
+    let parser = Parser::new_ext(
+        &source,
+        Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_MATH,
+    );
+
+    let mut events = Vec::new();
+    let mut problems = Vec::new();
+    let mut code: Option<(String, String, usize)> = None;
+    let mut diagrams = 0;
+    let mut link_depth = 0;
+
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new(), range.start));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buffer, _)) = code.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let (lang, buffer, start) = match code.take() {
+                    Some(block) => block,
+                    None => continue,
+                };
+                if !is_diagram_language(&lang) {
+                    events.push(Event::Html(render_code_block(&lang, &buffer).into()));
+                    continue;
+                }
+
+                diagrams += 1;
+                let id_prefix = format!("diagram-{}-", diagrams);
+                match render_diagram(&lang, &buffer, &id_prefix) {
+                    Ok(svg) => events.push(Event::Html(
+                        format!(
+                            "<div class=\"diagram diagram-{}\">{}</div>\n",
+                            escape_html(&lang.to_lowercase()),
+                            svg
+                        )
+                        .into(),
+                    )),
+                    Err(e) => {
+                        // The fence line comes before the diagram's first line
+                        let (fence_line, _) = note_position(start);
+                        let line = fence_line + e.line.unwrap_or(0);
+                        events.push(Event::Html(
+                            format!(
+                                "<div class=\"render-error\" data-line=\"{}\">{}</div>\n{}",
+                                line,
+                                escape_html(&e.message),
+                                render_code_block(&lang, &buffer)
+                            )
+                            .into(),
+                        ));
+                        problems.push(RenderProblem {
+                            kind: RenderProblemKind::Diagram,
+                            line,
+                            column: 1,
+                            message: e.message,
+                        });
+                    }
+                }
+            }
+            Event::InlineMath(tex) | Event::DisplayMath(tex) => {
+                let display = source[range.clone()].starts_with("$$");
+                match latex_to_mathml(&tex, display) {
+                    Ok(mathml) => events.push(Event::InlineHtml(mathml.into())),
+                    Err(e) => {
+                        let tex_start = source[range.clone()]
+                            .find(&*tex)
+                            .map_or(range.start, |i| range.start + i);
+                        let (line, column) = note_position(tex_start + e.offset);
+                        events.push(Event::InlineHtml(
+                            format!(
+                                "<code class=\"math-error\" title=\"{}\" data-line=\"{}\">{}</code>",
+                                escape_html(&e.message),
+                                line,
+                                escape_html(&source[range])
+                            )
+                            .into(),
+                        ));
+                        problems.push(RenderProblem {
+                            kind: RenderProblemKind::Math,
+                            line,
+                            column,
+                            message: e.message,
+                        });
+                    }
+                }
+            }
+            Event::Start(Tag::Link { .. }) => {
+                link_depth += 1;
+                events.push(event);
+            }
+            Event::End(TagEnd::Link) => {
+                link_depth -= 1;
+                events.push(event);
+            }
+            Event::Text(text) if link_depth == 0 => autolink(text, &mut events),
+            event => events.push(event),
+        }
+    }
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events.into_iter());
+
+    for (i, link) in links.iter().enumerate().rev() {
+        rendered = rendered.replace(&placeholder(i), &resolver.anchor(link));
+    }
+
+    let css = options
+        .syntax_colors
+        .as_ref()
+        .and_then(|colors| {
+            css_for_theme_with_class_style(
+                &code_theme(colors),
+                ClassStyle::SpacedPrefixed {
+                    prefix: CLASS_PREFIX,
+                },
+            )
+            .ok()
+        })
+        .unwrap_or_default();
+
+    FsResult::ok(RenderedMarkdown {
+        html: sanitize(&rendered),
+        css,
+        problems,
+    })
+}
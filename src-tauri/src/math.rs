@@ -0,0 +1,1055 @@
+//! LaTeX math to MathML, for `$inline$` and `$$display$$` math in notes
+//!
+//! Covers the commonly used subset of LaTeX math: symbols, scripts,
+//! fractions, roots, fonts, accents, `\left`/`\right` fences and matrix-like
+//! environments. Anything else is reported as an error with its position.
+
+/// Why a formula could not be converted
+#[derive(Debug, Clone)]
+pub(crate) struct MathError {
+    pub message: String,
+    /// Byte offset in the formula
+    pub offset: usize,
+}
+
+/// Letters, symbols and operators: command name -> (element, text)
+const SYMBOLS: &[(&str, &str, &str)] = &[
+    ("alpha", "mi", "α"),
+    ("beta", "mi", "β"),
+    ("gamma", "mi", "γ"),
+    ("delta", "mi", "δ"),
+    ("epsilon", "mi", "ϵ"),
+    ("varepsilon", "mi", "ε"),
+    ("zeta", "mi", "ζ"),
+    ("eta", "mi", "η"),
+    ("theta", "mi", "θ"),
+    ("vartheta", "mi", "ϑ"),
+    ("iota", "mi", "ι"),
+    ("kappa", "mi", "κ"),
+    ("lambda", "mi", "λ"),
+    ("mu", "mi", "μ"),
+    ("nu", "mi", "ν"),
+    ("xi", "mi", "ξ"),
+    ("pi", "mi", "π"),
+    ("varpi", "mi", "ϖ"),
+    ("rho", "mi", "ρ"),
+    ("varrho", "mi", "ϱ"),
+    ("sigma", "mi", "σ"),
+    ("varsigma", "mi", "ς"),
+    ("tau", "mi", "τ"),
+    ("upsilon", "mi", "υ"),
+    ("phi", "mi", "ϕ"),
+    ("varphi", "mi", "φ"),
+    ("chi", "mi", "χ"),
+    ("psi", "mi", "ψ"),
+    ("omega", "mi", "ω"),
+    ("Gamma", "mi", "Γ"),
+    ("Delta", "mi", "Δ"),
+    ("Theta", "mi", "Θ"),
+    ("Lambda", "mi", "Λ"),
+    ("Xi", "mi", "Ξ"),
+    ("Pi", "mi", "Π"),
+    ("Sigma", "mi", "Σ"),
+    ("Upsilon", "mi", "Υ"),
+    ("Phi", "mi", "Φ"),
+    ("Psi", "mi", "Ψ"),
+    ("Omega", "mi", "Ω"),
+    ("infty", "mi", "∞"),
+    ("partial", "mi", "∂"),
+    ("nabla", "mi", "∇"),
+    ("emptyset", "mi", "∅"),
+    ("varnothing", "mi", "∅"),
+    ("ell", "mi", "ℓ"),
+    ("hbar", "mi", "ℏ"),
+    ("Re", "mi", "ℜ"),
+    ("Im", "mi", "ℑ"),
+    ("aleph", "mi", "ℵ"),
+    ("imath", "mi", "ı"),
+    ("jmath", "mi", "ȷ"),
+    ("prime", "mo", "′"),
+    ("times", "mo", "×"),
+    ("cdot", "mo", "⋅"),
+    ("div", "mo", "÷"),
+    ("pm", "mo", "±"),
+    ("mp", "mo", "∓"),
+    ("ast", "mo", "∗"),
+    ("star", "mo", "⋆"),
+    ("circ", "mo", "∘"),
+    ("bullet", "mo", "∙"),
+    ("oplus", "mo", "⊕"),
+    ("ominus", "mo", "⊖"),
+    ("otimes", "mo", "⊗"),
+    ("odot", "mo", "⊙"),
+    ("wedge", "mo", "∧"),
+    ("land", "mo", "∧"),
+    ("vee", "mo", "∨"),
+    ("lor", "mo", "∨"),
+    ("neg", "mo", "¬"),
+    ("lnot", "mo", "¬"),
+    ("cup", "mo", "∪"),
+    ("cap", "mo", "∩"),
+    ("setminus", "mo", "∖"),
+    ("leq", "mo", "≤"),
+    ("le", "mo", "≤"),
+    ("geq", "mo", "≥"),
+    ("ge", "mo", "≥"),
+    ("neq", "mo", "≠"),
+    ("ne", "mo", "≠"),
+    ("ll", "mo", "≪"),
+    ("gg", "mo", "≫"),
+    ("approx", "mo", "≈"),
+    ("equiv", "mo", "≡"),
+    ("sim", "mo", "∼"),
+    ("simeq", "mo", "≃"),
+    ("cong", "mo", "≅"),
+    ("propto", "mo", "∝"),
+    ("in", "mo", "∈"),
+    ("notin", "mo", "∉"),
+    ("ni", "mo", "∋"),
+    ("subset", "mo", "⊂"),
+    ("subseteq", "mo", "⊆"),
+    ("supset", "mo", "⊃"),
+    ("supseteq", "mo", "⊇"),
+    ("mid", "mo", "∣"),
+    ("parallel", "mo", "∥"),
+    ("perp", "mo", "⊥"),
+    ("forall", "mo", "∀"),
+    ("exists", "mo", "∃"),
+    ("nexists", "mo", "∄"),
+    ("to", "mo", "→"),
+    ("rightarrow", "mo", "→"),
+    ("leftarrow", "mo", "←"),
+    ("gets", "mo", "←"),
+    ("leftrightarrow", "mo", "↔"),
+    ("Rightarrow", "mo", "⇒"),
+    ("Leftarrow", "mo", "⇐"),
+    ("Leftrightarrow", "mo", "⇔"),
+    ("implies", "mo", "⟹"),
+    ("iff", "mo", "⟺"),
+    ("mapsto", "mo", "↦"),
+    ("uparrow", "mo", "↑"),
+    ("downarrow", "mo", "↓"),
+    ("angle", "mo", "∠"),
+    ("triangle", "mo", "△"),
+    ("ldots", "mo", "…"),
+    ("dots", "mo", "…"),
+    ("cdots", "mo", "⋯"),
+    ("vdots", "mo", "⋮"),
+    ("ddots", "mo", "⋱"),
+    ("langle", "mo", "⟨"),
+    ("rangle", "mo", "⟩"),
+    ("lfloor", "mo", "⌊"),
+    ("rfloor", "mo", "⌋"),
+    ("lceil", "mo", "⌈"),
+    ("rceil", "mo", "⌉"),
+    ("vert", "mo", "|"),
+    ("Vert", "mo", "‖"),
+    ("|", "mo", "‖"),
+    ("{", "mo", "{"),
+    ("}", "mo", "}"),
+    ("#", "mo", "#"),
+    ("$", "mo", "$"),
+    ("%", "mo", "%"),
+    ("&", "mo", "&"),
+    ("_", "mo", "_"),
+];
+
+/// Large operators whose scripts become limits in display math
+const LARGE_OPERATORS: &[(&str, &str)] = &[
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("coprod", "∐"),
+    ("bigcup", "⋃"),
+    ("bigcap", "⋂"),
+    ("bigvee", "⋁"),
+    ("bigwedge", "⋀"),
+    ("bigoplus", "⨁"),
+    ("bigotimes", "⨂"),
+];
+
+/// Integrals keep their scripts at the side
+const INTEGRALS: &[(&str, &str)] = &[("int", "∫"), ("iint", "∬"), ("iiint", "∭"), ("oint", "∮")];
+
+/// Function names set upright; the flag marks names that take limits
+const FUNCTIONS: &[(&str, bool)] = &[
+    ("sin", false),
+    ("cos", false),
+    ("tan", false),
+    ("cot", false),
+    ("sec", false),
+    ("csc", false),
+    ("arcsin", false),
+    ("arccos", false),
+    ("arctan", false),
+    ("sinh", false),
+    ("cosh", false),
+    ("tanh", false),
+    ("log", false),
+    ("ln", false),
+    ("lg", false),
+    ("exp", false),
+    ("arg", false),
+    ("deg", false),
+    ("dim", false),
+    ("ker", false),
+    ("hom", false),
+    ("lim", true),
+    ("liminf", true),
+    ("limsup", true),
+    ("max", true),
+    ("min", true),
+    ("sup", true),
+    ("inf", true),
+    ("det", true),
+    ("gcd", true),
+    ("Pr", true),
+];
+
+/// Accents: command -> (mark, stretchy, under)
+const ACCENTS: &[(&str, &str, bool, bool)] = &[
+    ("hat", "^", false, false),
+    ("widehat", "^", true, false),
+    ("check", "ˇ", false, false),
+    ("tilde", "~", false, false),
+    ("widetilde", "~", true, false),
+    ("bar", "¯", false, false),
+    ("overline", "‾", true, false),
+    ("vec", "→", false, false),
+    ("overrightarrow", "→", true, false),
+    ("overleftarrow", "←", true, false),
+    ("dot", "˙", false, false),
+    ("ddot", "¨", false, false),
+    ("underline", "_", true, true),
+];
+
+/// Horizontal spaces in em
+const SPACES: &[(&str, &str)] = &[
+    (",", "0.167em"),
+    (":", "0.222em"),
+    (">", "0.222em"),
+    (";", "0.278em"),
+    ("!", "-0.167em"),
+    (" ", "0.333em"),
+    ("quad", "1em"),
+    ("qquad", "2em"),
+];
+
+/// Letter styles set with `\mathbf`, `\mathbb`, ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variant {
+    Normal,
+    Bold,
+    BoldItalic,
+    DoubleStruck,
+    Script,
+    Fraktur,
+    SansSerif,
+    Monospace,
+}
+
+impl Variant {
+    fn from_command(name: &str) -> Option<Variant> {
+        Some(match name {
+            "mathrm" | "mathup" | "rm" => Variant::Normal,
+            "mathbf" | "bf" => Variant::Bold,
+            "boldsymbol" | "bm" => Variant::BoldItalic,
+            "mathbb" => Variant::DoubleStruck,
+            "mathcal" | "mathscr" => Variant::Script,
+            "mathfrak" => Variant::Fraktur,
+            "mathsf" => Variant::SansSerif,
+            "mathtt" => Variant::Monospace,
+            _ => return None,
+        })
+    }
+
+    /// The Mathematical Alphanumeric Symbols character for `c`, if any
+    fn apply(self, c: char) -> Option<char> {
+        let exception = match (self, c) {
+            (Variant::DoubleStruck, 'C') => Some('ℂ'),
+            (Variant::DoubleStruck, 'H') => Some('ℍ'),
+            (Variant::DoubleStruck, 'N') => Some('ℕ'),
+            (Variant::DoubleStruck, 'P') => Some('ℙ'),
+            (Variant::DoubleStruck, 'Q') => Some('ℚ'),
+            (Variant::DoubleStruck, 'R') => Some('ℝ'),
+            (Variant::DoubleStruck, 'Z') => Some('ℤ'),
+            (Variant::Script, 'B') => Some('ℬ'),
+            (Variant::Script, 'E') => Some('ℰ'),
+            (Variant::Script, 'F') => Some('ℱ'),
+            (Variant::Script, 'H') => Some('ℋ'),
+            (Variant::Script, 'I') => Some('ℐ'),
+            (Variant::Script, 'L') => Some('ℒ'),
+            (Variant::Script, 'M') => Some('ℳ'),
+            (Variant::Script, 'R') => Some('ℛ'),
+            (Variant::Script, 'e') => Some('ℯ'),
+            (Variant::Script, 'g') => Some('ℊ'),
+            (Variant::Script, 'o') => Some('ℴ'),
+            (Variant::Fraktur, 'C') => Some('ℭ'),
+            (Variant::Fraktur, 'H') => Some('ℌ'),
+            (Variant::Fraktur, 'I') => Some('ℑ'),
+            (Variant::Fraktur, 'R') => Some('ℜ'),
+            (Variant::Fraktur, 'Z') => Some('ℨ'),
+            _ => None,
+        };
+        if exception.is_some() {
+            return exception;
+        }
+
+        // (capital A, small a, digit 0)
+        let (upper, lower, digit) = match self {
+            Variant::Normal => return None,
+            Variant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+            Variant::BoldItalic => (0x1D468, 0x1D482, Some(0x1D7CE)),
+            Variant::Script => (0x1D49C, 0x1D4B6, None),
+            Variant::Fraktur => (0x1D504, 0x1D51E, None),
+            Variant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+            Variant::SansSerif => (0x1D5A0, 0x1D5BA, Some(0x1D7E2)),
+            Variant::Monospace => (0x1D670, 0x1D68A, Some(0x1D7F6)),
+        };
+        let code = match c {
+            'A'..='Z' => upper + (c as u32 - 'A' as u32),
+            'a'..='z' => lower + (c as u32 - 'a' as u32),
+            '0'..='9' => digit? + (c as u32 - '0' as u32),
+            _ => return None,
+        };
+        char::from_u32(code)
+    }
+}
+
+/// How scripts attach to an atom
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placement {
+    /// At the side
+    Side,
+    /// Above and below in display math (`\sum`, `\lim`)
+    Limits,
+}
+
+struct Atom {
+    mathml: String,
+    placement: Placement,
+}
+
+impl Atom {
+    fn new(mathml: String) -> Atom {
+        Atom {
+            mathml,
+            placement: Placement::Side,
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn token(element: &str, text: &str) -> String {
+    format!("<{0}>{1}</{0}>", element, escape(text))
+}
+
+fn fence(delimiter: &str) -> String {
+    match delimiter {
+        "" => String::new(),
+        d => format!("<mo fence=\"true\" stretchy=\"true\">{}</mo>", escape(d)),
+    }
+}
+
+struct TexParser<'a> {
+    tex: &'a str,
+    pos: usize,
+    display: bool,
+    variant: Option<Variant>,
+}
+
+impl<'a> TexParser<'a> {
+    fn error<T>(&self, message: impl Into<String>, offset: usize) -> Result<T, MathError> {
+        Err(MathError {
+            message: message.into(),
+            offset,
+        })
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.tex[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            // `%` starts a comment running to the end of the line
+            if trimmed.starts_with('%') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Whether the next token ends a row: end of input, `}`, `&`, `\\`,
+    /// `\right`, `\middle`, `\end` or the `close` character
+    fn at_row_end(&self, close: Option<char>) -> bool {
+        let rest = self.rest();
+        rest.is_empty()
+            || rest.starts_with('}')
+            || rest.starts_with('&')
+            || rest.starts_with("\\\\")
+            || close.is_some_and(|c| rest.starts_with(c))
+            || ["\\right", "\\middle", "\\end"].iter().any(|cmd| {
+                rest.starts_with(cmd)
+                    && !rest[cmd.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+            })
+    }
+
+    /// Parse atoms with their scripts up to the end of the row
+    fn parse_row(&mut self, close: Option<char>) -> Result<String, MathError> {
+        let mut row = String::new();
+        loop {
+            self.skip_whitespace();
+            if self.at_row_end(close) {
+                return Ok(row);
+            }
+            let atom = self.parse_atom()?;
+            row.push_str(&self.parse_scripts(atom)?);
+        }
+    }
+
+    /// Read a command name after `\`: a run of letters or a single character
+    fn command_name(&mut self) -> Result<&'a str, MathError> {
+        let start = self.pos;
+        self.pos += 1;
+        let rest = self.rest();
+        let len = match rest.find(|c: char| !c.is_ascii_alphabetic()) {
+            Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+            Some(n) => n,
+            None => rest.len(),
+        };
+        if len == 0 {
+            return self.error("Expected a command name after \\", start);
+        }
+        self.pos += len;
+        Ok(&self.tex[start + 1..self.pos])
+    }
+
+    /// A group `{...}` as an `mrow`, or a single token
+    fn parse_argument(&mut self) -> Result<String, MathError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => self.error("Missing argument", self.pos),
+            Some('}') => self.error("Missing argument", self.pos),
+            Some('{') => self.parse_group(),
+            Some('\\') => Ok(self.parse_atom()?.mathml),
+            Some(c) => {
+                self.pos += c.len_utf8();
+                Ok(self.char_token(c, &c.to_string()))
+            }
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<String, MathError> {
+        let open = self.pos;
+        self.pos += 1;
+        let row = self.parse_row(None)?;
+        if !self.rest().starts_with('}') {
+            return self.error("Missing } for this {", open);
+        }
+        self.pos += 1;
+        Ok(format!("<mrow>{}</mrow>", row))
+    }
+
+    /// The raw text of a `{...}` group (for `\text`, `\begin`, ...)
+    fn raw_group(&mut self) -> Result<&'a str, MathError> {
+        self.skip_whitespace();
+        if !self.rest().starts_with('{') {
+            return self.error("Expected {", self.pos);
+        }
+        let open = self.pos;
+        let mut depth = 0;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let text = &self.tex[open + 1..open + i];
+                        self.pos = open + i + 1;
+                        return Ok(text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.error("Missing } for this {", open)
+    }
+
+    /// A letter, digit or operator character in the current font
+    fn char_token(&self, c: char, text: &str) -> String {
+        if c.is_alphabetic() || c.is_ascii_digit() {
+            let element = if c.is_ascii_digit() { "mn" } else { "mi" };
+            return match self.variant {
+                Some(Variant::Normal) if element == "mi" => {
+                    format!("<mi mathvariant=\"normal\">{}</mi>", escape(text))
+                }
+                Some(variant) => {
+                    let styled: String = text
+                        .chars()
+                        .map(|c| variant.apply(c).unwrap_or(c))
+                        .collect();
+                    token(element, &styled)
+                }
+                None => token(element, text),
+            };
+        }
+        match c {
+            '-' => token("mo", "−"),
+            '*' => token("mo", "∗"),
+            '\'' => token("mo", "′"),
+            '~' => "<mspace width=\"0.333em\"></mspace>".to_string(),
+            _ => token("mo", text),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Atom, MathError> {
+        let start = self.pos;
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return self.error("Unexpected end of formula", start),
+        };
+
+        match c {
+            '{' => Ok(Atom::new(self.parse_group()?)),
+            '\\' => self.parse_command(),
+            '^' | '_' => self.error(format!("Missing base for {}", c), start),
+            '$' => self.error("Unexpected $", start),
+            '0'..='9' => {
+                // A number: digits with an optional decimal point
+                let rest = self.rest();
+                let mut len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                if rest[len..].starts_with('.')
+                    && rest[len + 1..].starts_with(|c: char| c.is_ascii_digit())
+                {
+                    len += 1 + rest[len + 1..]
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(rest.len() - len - 1);
+                }
+                self.pos += len;
+                Ok(Atom::new(self.char_token('0', &rest[..len])))
+            }
+            c => {
+                self.pos += c.len_utf8();
+                Ok(Atom::new(self.char_token(c, &c.to_string())))
+            }
+        }
+    }
+
+    fn parse_command(&mut self) -> Result<Atom, MathError> {
+        let start = self.pos;
+        let name = self.command_name()?;
+
+        if let Some((_, element, text)) = SYMBOLS.iter().find(|(n, _, _)| *n == name) {
+            let mathml = match (*element, self.variant) {
+                ("mi", Some(Variant::Bold | Variant::BoldItalic)) => {
+                    format!("<mi mathvariant=\"bold\">{}</mi>", escape(text))
+                }
+                ("mi", _) if text.chars().next().is_some_and(char::is_uppercase) => {
+                    format!("<mi mathvariant=\"normal\">{}</mi>", escape(text))
+                }
+                _ => token(element, text),
+            };
+            return Ok(Atom::new(mathml));
+        }
+        if let Some((_, symbol)) = LARGE_OPERATORS.iter().find(|(n, _)| *n == name) {
+            return Ok(Atom {
+                mathml: format!(
+                    "<mo largeop=\"true\" movablelimits=\"true\">{}</mo>",
+                    symbol
+                ),
+                placement: Placement::Limits,
+            });
+        }
+        if let Some((_, symbol)) = INTEGRALS.iter().find(|(n, _)| *n == name) {
+            return Ok(Atom::new(format!("<mo largeop=\"true\">{}</mo>", symbol)));
+        }
+        if let Some((_, limits)) = FUNCTIONS.iter().find(|(n, _)| *n == name) {
+            return Ok(Atom {
+                mathml: format!("<mi mathvariant=\"normal\">{}</mi>", name),
+                placement: if *limits {
+                    Placement::Limits
+                } else {
+                    Placement::Side
+                },
+            });
+        }
+        if let Some((_, width)) = SPACES.iter().find(|(n, _)| *n == name) {
+            return Ok(Atom::new(format!("<mspace width=\"{}\"></mspace>", width)));
+        }
+        if let Some((_, mark, stretchy, under)) = ACCENTS.iter().find(|(n, _, _, _)| *n == name) {
+            let base = self.parse_argument()?;
+            let (element, attribute) = if *under {
+                ("munder", "accentunder")
+            } else {
+                ("mover", "accent")
+            };
+            return Ok(Atom::new(format!(
+                "<{0} {1}=\"true\">{2}<mo stretchy=\"{3}\">{4}</mo></{0}>",
+                element, attribute, base, stretchy, mark
+            )));
+        }
+        if let Some(variant) = Variant::from_command(name) {
+            let outer = self.variant.replace(variant);
+            let argument = self.parse_argument();
+            self.variant = outer;
+            return Ok(Atom::new(argument?));
+        }
+
+        match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.parse_argument()?;
+                let denominator = self.parse_argument()?;
+                Ok(Atom::new(format!(
+                    "<mfrac>{}{}</mfrac>",
+                    numerator, denominator
+                )))
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let top = self.parse_argument()?;
+                let bottom = self.parse_argument()?;
+                Ok(Atom::new(format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    top, bottom
+                )))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.rest().starts_with('[') {
+                    let open = self.pos;
+                    self.pos += 1;
+                    let index = self.parse_row(Some(']'))?;
+                    if !self.rest().starts_with(']') {
+                        return self.error("Missing ] for this [", open);
+                    }
+                    self.pos += 1;
+                    let radicand = self.parse_argument()?;
+                    Ok(Atom::new(format!(
+                        "<mroot>{}<mrow>{}</mrow></mroot>",
+                        radicand, index
+                    )))
+                } else {
+                    Ok(Atom::new(format!(
+                        "<msqrt>{}</msqrt>",
+                        self.parse_argument()?
+                    )))
+                }
+            }
+            "text" | "textrm" | "textup" | "textnormal" | "mbox" | "textit" | "textbf" => {
+                Ok(Atom::new(token("mtext", self.raw_group()?)))
+            }
+            "operatorname" => {
+                let name = self.raw_group()?.trim();
+                Ok(Atom::new(format!(
+                    "<mi mathvariant=\"normal\">{}</mi>",
+                    escape(name)
+                )))
+            }
+            "overbrace" | "underbrace" => {
+                let base = self.parse_argument()?;
+                let (element, brace) = if name == "overbrace" {
+                    ("mover", "⏞")
+                } else {
+                    ("munder", "⏟")
+                };
+                Ok(Atom {
+                    mathml: format!(
+                        "<{0}>{1}<mo stretchy=\"true\">{2}</mo></{0}>",
+                        element, base, brace
+                    ),
+                    placement: Placement::Limits,
+                })
+            }
+            "left" => {
+                let open = self.delimiter()?;
+                let mut inner = self.parse_row(None)?;
+                while self.rest().starts_with("\\middle") {
+                    self.pos += "\\middle".len();
+                    inner.push_str(&fence(&self.delimiter()?));
+                    inner.push_str(&self.parse_row(None)?);
+                }
+                if !self.rest().starts_with("\\right") {
+                    return self.error("Missing \\right for this \\left", start);
+                }
+                self.pos += "\\right".len();
+                let close = self.delimiter()?;
+                Ok(Atom::new(format!(
+                    "<mrow>{}{}{}</mrow>",
+                    fence(&open),
+                    inner,
+                    fence(&close)
+                )))
+            }
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl"
+            | "biggr" | "Biggl" | "Biggr" => {
+                let size = match name.trim_end_matches(['l', 'r']) {
+                    "big" => "1.2em",
+                    "Big" => "1.8em",
+                    "bigg" => "2.4em",
+                    _ => "3em",
+                };
+                let delimiter = self.delimiter()?;
+                Ok(Atom::new(format!(
+                    "<mo minsize=\"{0}\" maxsize=\"{0}\">{1}</mo>",
+                    size,
+                    escape(&delimiter)
+                )))
+            }
+            "begin" => self.parse_environment(start),
+            "right" | "middle" => self.error(format!("\\{} without \\left", name), start),
+            "end" => self.error("\\end without \\begin", start),
+            _ => self.error(format!("Unknown command \\{}", name), start),
+        }
+    }
+
+    /// A fence delimiter after `\left`, `\right`, `\big`...; `.` is none
+    fn delimiter(&mut self) -> Result<String, MathError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('.') => {
+                self.pos += 1;
+                Ok(String::new())
+            }
+            Some('\\') => {
+                let name = self.command_name()?;
+                match SYMBOLS.iter().find(|(n, e, _)| *n == name && *e == "mo") {
+                    Some((_, _, text)) => Ok(text.to_string()),
+                    None => self.error(format!("\\{} is not a delimiter", name), start),
+                }
+            }
+            Some(c) if "()[]|/<>".contains(c) => {
+                self.pos += 1;
+                Ok(match c {
+                    '<' => "⟨".to_string(),
+                    '>' => "⟩".to_string(),
+                    c => c.to_string(),
+                })
+            }
+            _ => self.error("Missing delimiter", start),
+        }
+    }
+
+    /// `\begin{name} ... \end{name}` for matrices, cases and alignments
+    fn parse_environment(&mut self, start: usize) -> Result<Atom, MathError> {
+        let name = self.raw_group()?.trim();
+        let (open, close, columnalign) = match name {
+            "matrix" | "smallmatrix" | "array" | "gathered" | "gather" | "gather*" => {
+                ("", "", "center")
+            }
+            "pmatrix" => ("(", ")", "center"),
+            "bmatrix" => ("[", "]", "center"),
+            "Bmatrix" => ("{", "}", "center"),
+            "vmatrix" => ("|", "|", "center"),
+            "Vmatrix" => ("‖", "‖", "center"),
+            "cases" => ("{", "", "left left"),
+            "aligned" | "align" | "align*" | "split" | "alignat" | "alignat*" => {
+                ("", "", "right left")
+            }
+            _ => return self.error(format!("Unknown environment {}", name), start),
+        };
+        // Column specs (`{cc}`) and alignat's column count are not needed
+        if matches!(name, "array" | "alignat" | "alignat*") {
+            self.raw_group()?;
+        }
+
+        let mut rows: Vec<Vec<String>> = vec![Vec::new()];
+        loop {
+            let cell = self.parse_row(None)?;
+            if let Some(row) = rows.last_mut() {
+                row.push(cell);
+            }
+
+            let rest = self.rest();
+            if rest.starts_with('&') {
+                self.pos += 1;
+            } else if rest.starts_with("\\\\") {
+                self.pos += 2;
+                // Skip an optional row spacing such as `\\[2pt]`
+                self.skip_whitespace();
+                if self.rest().starts_with('[') {
+                    self.pos += self.rest().find(']').map_or(0, |i| i + 1);
+                }
+                rows.push(Vec::new());
+            } else if rest.starts_with("\\end") {
+                self.pos += "\\end".len();
+                let end_name = self.raw_group()?.trim();
+                if end_name != name {
+                    return self.error(
+                        format!("\\begin{{{}}} ended by \\end{{{}}}", name, end_name),
+                        start,
+                    );
+                }
+                break;
+            } else if rest.is_empty() {
+                return self.error(format!("Missing \\end{{{}}}", name), start);
+            } else {
+                let c = rest.chars().next().unwrap_or(' ');
+                return self.error(format!("Unexpected {}", c), self.pos);
+            }
+        }
+
+        // A trailing `\\` leaves an empty last row
+        if rows
+            .last()
+            .is_some_and(|row| row.iter().all(|cell| cell.is_empty()))
+            && rows.len() > 1
+        {
+            rows.pop();
+        }
+
+        let body: String = rows
+            .iter()
+            .map(|row| {
+                let cells: String = row
+                    .iter()
+                    .map(|cell| format!("<mtd>{}</mtd>", cell))
+                    .collect();
+                format!("<mtr>{}</mtr>", cells)
+            })
+            .collect();
+        Ok(Atom::new(format!(
+            "<mrow>{}<mtable columnalign=\"{}\">{}</mtable>{}</mrow>",
+            fence(open),
+            columnalign,
+            body,
+            fence(close)
+        )))
+    }
+
+    /// Attach any `^`, `_` and `'` scripts that follow an atom
+    fn parse_scripts(&mut self, base: Atom) -> Result<String, MathError> {
+        let mut sub: Option<String> = None;
+        let mut sup: Option<String> = None;
+
+        loop {
+            self.skip_whitespace();
+            let at = self.pos;
+            match self.peek() {
+                Some('_') => {
+                    self.pos += 1;
+                    if sub.is_some() {
+                        return self.error("Double subscript", at);
+                    }
+                    sub = Some(self.parse_argument()?);
+                }
+                Some('^') => {
+                    self.pos += 1;
+                    if sup.is_some() {
+                        return self.error("Double superscript", at);
+                    }
+                    sup = Some(self.parse_argument()?);
+                }
+                Some('\'') => {
+                    let primes = self.rest().len() - self.rest().trim_start_matches('\'').len();
+                    self.pos += primes;
+                    if sup.is_some() {
+                        return self.error("Double superscript", at);
+                    }
+                    sup = Some(token("mo", &"′".repeat(primes)));
+                }
+                _ => break,
+            }
+        }
+
+        let limits = base.placement == Placement::Limits && self.display;
+        let (under, over, both) = if limits {
+            ("munder", "mover", "munderover")
+        } else {
+            ("msub", "msup", "msubsup")
+        };
+        Ok(match (sub, sup) {
+            (None, None) => base.mathml,
+            (Some(sub), None) => format!("<{0}>{1}{2}</{0}>", under, base.mathml, sub),
+            (None, Some(sup)) => format!("<{0}>{1}{2}</{0}>", over, base.mathml, sup),
+            (Some(sub), Some(sup)) => {
+                format!("<{0}>{1}{2}{3}</{0}>", both, base.mathml, sub, sup)
+            }
+        })
+    }
+}
+
+/// Convert a LaTeX formula to a `<math>` element; `display` is for `$$...$$`
+pub(crate) fn latex_to_mathml(tex: &str, display: bool) -> Result<String, MathError> {
+    let mut parser = TexParser {
+        tex,
+        pos: 0,
+        display,
+        variant: None,
+    };
+
+    let row = parser.parse_row(None)?;
+    if let Some(c) = parser.peek() {
+        let what = if parser.rest().starts_with("\\\\") {
+            "\\\\ outside of an environment".to_string()
+        } else {
+            format!("Unexpected {}", c)
+        };
+        return parser.error(what, parser.pos);
+    }
+
+    Ok(format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"{}\"><semantics><mrow>{}</mrow><annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { "block" } else { "inline" },
+        row,
+        escape(tex.trim())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The MathML between `<semantics>` and the TeX annotation
+    fn mathml(tex: &str) -> String {
+        let math = latex_to_mathml(tex, true).unwrap();
+        let start = math.find("<semantics>").unwrap() + "<semantics>".len();
+        let end = math.find("<annotation").unwrap();
+        math[start..end].to_string()
+    }
+
+    fn error(tex: &str) -> (String, usize) {
+        let e = latex_to_mathml(tex, true).unwrap_err();
+        (e.message, e.offset)
+    }
+
+    #[test]
+    fn converts_fractions_and_roots() {
+        assert_eq!(
+            mathml(r"\frac{a}{b}"),
+            "<mrow><mfrac><mrow><mi>a</mi></mrow><mrow><mi>b</mi></mrow></mfrac></mrow>"
+        );
+        assert_eq!(
+            mathml(r"\sqrt[3]{x}"),
+            "<mrow><mroot><mrow><mi>x</mi></mrow><mrow><mn>3</mn></mrow></mroot></mrow>"
+        );
+    }
+
+    #[test]
+    fn converts_scripts() {
+        assert_eq!(
+            mathml("x_i^2"),
+            "<mrow><msubsup><mi>x</mi><mi>i</mi><mn>2</mn></msubsup></mrow>"
+        );
+        assert_eq!(
+            mathml("x''"),
+            "<mrow><msup><mi>x</mi><mo>′′</mo></msup></mrow>"
+        );
+
+        // Large operators take limits in display math only
+        assert!(mathml(r"\sum_{i=1}^n i").contains("<munderover>"));
+        assert!(latex_to_mathml(r"\sum_{i=1}^n i", false)
+            .unwrap()
+            .contains("<msubsup>"));
+
+        assert_eq!(error("x_1^2^3"), ("Double superscript".to_string(), 5));
+        assert_eq!(error("x^"), ("Missing argument".to_string(), 2));
+    }
+
+    #[test]
+    fn converts_left_right_fences() {
+        assert_eq!(
+            mathml(r"\left( x \right)"),
+            "<mrow><mrow><mo fence=\"true\" stretchy=\"true\">(</mo><mi>x</mi><mo fence=\"true\" stretchy=\"true\">)</mo></mrow></mrow>"
+        );
+
+        // `.` is an invisible delimiter
+        assert_eq!(
+            mathml(r"\left\{ x \right."),
+            "<mrow><mrow><mo fence=\"true\" stretchy=\"true\">{</mo><mi>x</mi></mrow></mrow>"
+        );
+
+        assert_eq!(
+            error(r"\left( x"),
+            ("Missing \\right for this \\left".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn converts_environments() {
+        assert_eq!(
+            mathml(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}"),
+            "<mrow><mrow><mo fence=\"true\" stretchy=\"true\">(</mo><mtable columnalign=\"center\"><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr><mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr></mtable><mo fence=\"true\" stretchy=\"true\">)</mo></mrow></mrow>"
+        );
+        assert!(
+            mathml(r"\begin{cases} 1 & x>0 \\ 0 & \text{else} \end{cases}")
+                .contains("<mtable columnalign=\"left left\">")
+        );
+
+        assert_eq!(
+            error(r"\begin{matrix} a \end{pmatrix}"),
+            ("\\begin{matrix} ended by \\end{pmatrix}".to_string(), 0)
+        );
+        assert_eq!(
+            error(r"\begin{foo} a \end{foo}"),
+            ("Unknown environment foo".to_string(), 0)
+        );
+        assert_eq!(
+            error(r"a \\ b"),
+            ("\\\\ outside of an environment".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn reports_unbalanced_braces() {
+        assert_eq!(error("{a"), ("Missing } for this {".to_string(), 0));
+        assert_eq!(error("a}"), ("Unexpected }".to_string(), 1));
+        assert_eq!(error(r"\frac{a}"), ("Missing argument".to_string(), 8));
+        assert_eq!(error(r"\sqrt[3"), ("Missing ] for this [".to_string(), 5));
+    }
+
+    #[test]
+    fn reports_trailing_backslash() {
+        assert_eq!(
+            error("a \\"),
+            ("Expected a command name after \\".to_string(), 2)
+        );
+        assert_eq!(
+            error("\\"),
+            ("Expected a command name after \\".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn handles_multibyte_input() {
+        assert_eq!(
+            mathml(r"é + \alpha"),
+            "<mrow><mi>é</mi><mo>+</mo><mi>α</mi></mrow>"
+        );
+        assert_eq!(
+            mathml("ü^{ß}"),
+            "<mrow><msup><mi>ü</mi><mrow><mi>ß</mi></mrow></msup></mrow>"
+        );
+        assert_eq!(mathml(r"\text{héllo}"), "<mrow><mtext>héllo</mtext></mrow>");
+
+        // Offsets are in bytes, and errors never split a character
+        assert_eq!(error("é}"), ("Unexpected }".to_string(), 2));
+        assert_eq!(error(r"a\é"), ("Unknown command \\é".to_string(), 1));
+        assert_eq!(error(r"\text{é"), ("Missing } for this {".to_string(), 5));
+        assert_eq!(error(r"\left é"), ("Missing delimiter".to_string(), 6));
+    }
+
+    #[test]
+    fn escapes_text_and_annotation() {
+        let math = latex_to_mathml("a < b", false).unwrap();
+        assert!(math
+            .starts_with("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\">"));
+        assert!(math.contains("<mo>&lt;</mo>"));
+        assert!(math.contains("<annotation encoding=\"application/x-tex\">a &lt; b</annotation>"));
+    }
+}
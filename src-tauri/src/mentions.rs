@@ -0,0 +1,264 @@
+//! Unlinked mentions: plain-text occurrences of a note's name or aliases in
+//! other notes, and turning them into wiki links
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::history::Journal;
+use crate::links::{format_wiki_link, line_at, LinkRewrite};
+use crate::{frontmatter, non_text_ranges, parse_wiki_links, FsResult, NoteIndex};
+
+/// Characters of context kept on each side of a mention in its snippet
+const SNIPPET_CONTEXT: usize = 60;
+
+/// A plain-text occurrence of a note's name or alias
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnlinkedMention {
+    pub source_path: String,
+    pub source_name: String,
+    /// Line number (1-based)
+    pub line: usize,
+    /// Byte offsets of the mention in the source note
+    pub start: usize,
+    pub end: usize,
+    /// The text as written in the source note
+    pub text: String,
+    /// Whether the text matched an alias rather than the note name
+    pub is_alias: bool,
+    /// The surrounding line, shortened to about `SNIPPET_CONTEXT` characters
+    /// on each side
+    pub snippet: String,
+    /// Byte offsets of the mention within `snippet`
+    pub snippet_start: usize,
+    pub snippet_end: usize,
+}
+
+/// Byte ranges where a mention is not plain text: frontmatter, code,
+/// comments, existing links of either style and bare URLs
+fn excluded_ranges(content: &str) -> Vec<(usize, usize)> {
+    let mut ranges = non_text_ranges(content);
+    if let Some(fm) = frontmatter::parse_frontmatter(content) {
+        ranges.push((0, fm.body_start));
+    }
+    ranges.extend(parse_wiki_links(content).iter().map(|l| (l.start, l.end)));
+
+    let re =
+        Regex::new(r"!?\[[^\]\n]*\]\([^)\n]*\)|<?[a-zA-Z][a-zA-Z0-9+.-]*://[^\s>)]*>?").unwrap();
+    ranges.extend(re.find_iter(content).map(|m| (m.start(), m.end())));
+    ranges
+}
+
+/// Whether `content[start..end]` stands alone as a word or phrase
+fn at_word_boundary(content: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let before = content[..start].chars().next_back();
+    let after = content[end..].chars().next();
+    !before.is_some_and(is_word) && !after.is_some_and(is_word)
+}
+
+/// Case-insensitive, non-overlapping matches of any term, longest term first.
+/// Returns `(start, end, term index)` sorted by position.
+fn find_mentions(content: &str, terms: &[String]) -> Vec<(usize, usize, usize)> {
+    let excluded = excluded_ranges(content);
+    let mut order: Vec<usize> = (0..terms.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(terms[*i].len()));
+
+    let mut found: Vec<(usize, usize, usize)> = Vec::new();
+    for i in order {
+        let re = match Regex::new(&format!("(?i){}", regex::escape(&terms[i]))) {
+            Ok(re) => re,
+            Err(_) => continue,
+        };
+        for m in re.find_iter(content) {
+            let (start, end) = (m.start(), m.end());
+            let overlaps = |(s, e): &(usize, usize)| start < *e && *s < end;
+            if at_word_boundary(content, start, end)
+                && !excluded.iter().any(overlaps)
+                && !found.iter().any(|(s, e, _)| overlaps(&(*s, *e)))
+            {
+                found.push((start, end, i));
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// The line around `start..end`, trimmed to some context on each side, with
+/// the mention's offsets within it
+fn snippet(content: &str, start: usize, end: usize) -> (String, usize, usize) {
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[end..].find('\n').map_or(content.len(), |i| end + i);
+
+    let from = content[line_start..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(line_start, |(i, _)| line_start + i);
+    let to = content[end..line_end]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(line_end, |(i, _)| end + i);
+
+    let prefix = if from > line_start { "…" } else { "" };
+    let suffix = if to < line_end { "…" } else { "" };
+    let before = content[from..start].trim_start();
+    let text = format!(
+        "{}{}{}{}",
+        prefix,
+        before,
+        &content[start..to].trim_end_matches('\r'),
+        suffix
+    );
+    let snippet_start = prefix.len() + before.len();
+    (text, snippet_start, snippet_start + (end - start))
+}
+
+/// The note a name refers to and the terms it can be mentioned by: its name
+/// and the aliases that lead to it. The flag marks aliases.
+fn mention_terms(index: &NoteIndex, note_name: &str) -> Option<(usize, Vec<(String, bool)>)> {
+    let note = index.resolve(note_name)?;
+    let mut terms = vec![(index.name(note), false)];
+    terms.extend(
+        index
+            .aliases(note)
+            .iter()
+            .filter(|a| index.resolve(a) == Some(note))
+            .map(|a| (a.clone(), true)),
+    );
+    Some((note, terms))
+}
+
+/// Find plain-text mentions of a note's name and aliases in other notes -
+/// Tauri command
+#[tauri::command]
+pub fn get_unlinked_mentions(
+    vault_path: String,
+    note_name: String,
+) -> FsResult<Vec<UnlinkedMention>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::ok(vec![]);
+    }
+
+    let index = NoteIndex::build(&vault);
+    let (note, terms) = match mention_terms(&index, &note_name) {
+        Some(found) => found,
+        None => return FsResult::ok(vec![]),
+    };
+    let words: Vec<String> = terms.iter().map(|(t, _)| t.clone()).collect();
+    let mut mentions = Vec::new();
+
+    for (i, path) in index.notes.iter().enumerate() {
+        if i == note {
+            continue;
+        }
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        for (start, end, term) in find_mentions(&content, &words) {
+            let (snippet, snippet_start, snippet_end) = snippet(&content, start, end);
+            mentions.push(UnlinkedMention {
+                source_path: path.to_string_lossy().to_string(),
+                source_name: index.name(i),
+                line: line_at(&content, start),
+                start,
+                end,
+                text: content[start..end].to_string(),
+                is_alias: terms[term].1,
+                snippet,
+                snippet_start,
+                snippet_end,
+            });
+        }
+    }
+
+    FsResult::ok(mentions)
+}
+
+/// Turn the given mentions of a note into wiki links - Tauri command
+///
+/// Mentions whose text has changed since they were found are skipped. The
+/// link keeps the mention's text: `[[Text]]` when that already leads to the
+/// note, otherwise `[[target|Text]]`.
+#[tauri::command]
+pub fn link_unlinked_mentions(
+    vault_path: String,
+    note_name: String,
+    mentions: Vec<UnlinkedMention>,
+) -> FsResult<LinkRewrite> {
+    let vault = PathBuf::from(&vault_path);
+    let index = NoteIndex::build(&vault);
+    let (note, _) = match mention_terms(&index, &note_name) {
+        Some(found) => found,
+        None => return FsResult::err("Note not found"),
+    };
+    let target = index.link_target(note);
+
+    let mut by_source: BTreeMap<String, Vec<UnlinkedMention>> = BTreeMap::new();
+    for mention in mentions {
+        by_source
+            .entry(mention.source_path.clone())
+            .or_default()
+            .push(mention);
+    }
+
+    let mut summary = LinkRewrite::default();
+    let label = format!("Link mentions of \"{}\"", index.name(note));
+    let mut journal = Journal::new(Some(vault.clone()), label);
+    for (source, mut mentions) in by_source {
+        let path = PathBuf::from(&source);
+        let mut content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) => return FsResult::err(&format!("Failed to read {}: {}", source, e)),
+        };
+        let original = content.clone();
+        let excluded = excluded_ranges(&content);
+
+        // Replace from the end so earlier offsets stay valid
+        mentions.sort_by_key(|m| std::cmp::Reverse(m.start));
+        mentions.dedup_by_key(|m| m.start);
+        let mut count = 0;
+
+        for mention in mentions {
+            let (start, end) = (mention.start, mention.end);
+            let unchanged = content
+                .get(start..end)
+                .is_some_and(|text| text == mention.text)
+                && at_word_boundary(&content, start, end)
+                && !excluded.iter().any(|(s, e)| start < *e && *s < end);
+            if !unchanged {
+                continue;
+            }
+
+            let link = if index.resolve_from(&mention.text, Some(&path)) == Some(note) {
+                format_wiki_link(&mention.text, None, None)
+            } else {
+                format_wiki_link(&target, None, Some(&mention.text))
+            };
+            content.replace_range(start..end, &link);
+            count += 1;
+        }
+
+        if count == 0 {
+            continue;
+        }
+        if let Err(e) = journal.write(&path, &original, &content) {
+            return FsResult::err(&format!("Failed to update {}: {}", source, e));
+        }
+        summary.changed_notes.push(source);
+        summary.links_updated += count;
+    }
+
+    match journal.finish() {
+        Ok(id) => summary.operation_id = id,
+        Err(e) => return FsResult::err(&format!("Linked, but failed to save history: {}", e)),
+    }
+    FsResult::ok(summary)
+}
@@ -0,0 +1,290 @@
+//! Stable note IDs that survive renames and moves
+//!
+//! A note's ID is the `id:` key of its frontmatter when it has one; otherwise
+//! the vault's sidecar index (`.open-note/note-ids.json`) assigns one, so
+//! notes are never rewritten just to be tracked. The index remembers each
+//! note's path and a content hash, which is how notes renamed or moved
+//! outside the app are found again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::blocks::unique_id;
+use crate::{collect_notes, frontmatter, write_file_atomic, FsResult};
+
+/// Folder inside a vault for app data
+pub const APP_DIR: &str = ".open-note";
+
+/// Sidecar index file inside `APP_DIR`
+const IDS_FILE: &str = "note-ids.json";
+
+/// Where the sidecar index last saw a note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct IdEntry {
+    /// Path relative to the vault, with `/` separators
+    path: String,
+    /// Hash of the note content when last seen
+    hash: u64,
+}
+
+/// The sidecar index of a vault: ID -> last known location
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NoteIds {
+    notes: BTreeMap<String, IdEntry>,
+}
+
+fn ids_path(vault: &Path) -> PathBuf {
+    vault.join(APP_DIR).join(IDS_FILE)
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn relative(vault: &Path, path: &Path) -> String {
+    path.strip_prefix(vault)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// The `id:` key of a note's frontmatter
+fn frontmatter_id(content: &str) -> Option<String> {
+    frontmatter::parse_frontmatter(content)?
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+impl NoteIds {
+    fn load(vault: &Path) -> NoteIds {
+        fs::read_to_string(ids_path(vault))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, vault: &Path) -> Result<(), String> {
+        let path = ids_path(vault);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_file_atomic(&path, &json).map_err(|e| e.to_string())
+    }
+
+    /// ID of the note at `relative_path`, according to the index
+    fn id_at(&self, relative_path: &str) -> Option<String> {
+        self.notes
+            .iter()
+            .find(|(_, entry)| entry.path == relative_path)
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Find every indexed note again after renames and moves.
+    ///
+    /// A note whose frontmatter `id` matches wins; otherwise the note is
+    /// still at its recorded path, or it is an untracked note with the same
+    /// content, or the only untracked note with the same file name. Entries
+    /// that match nothing are kept in case the note comes back.
+    fn reconcile(&mut self, vault: &PathBuf) {
+        let notes: Vec<(String, String)> = collect_notes(vault)
+            .iter()
+            .filter_map(|p| Some((relative(vault, p), fs::read_to_string(p).ok()?)))
+            .collect();
+        let own_ids: Vec<Option<String>> = notes.iter().map(|(_, c)| frontmatter_id(c)).collect();
+
+        let mut claimed: HashSet<usize> = HashSet::new();
+        let mut found: BTreeMap<String, usize> = BTreeMap::new();
+
+        // Frontmatter IDs are authoritative
+        for (i, id) in own_ids.iter().enumerate() {
+            if let Some(id) = id {
+                if self.notes.contains_key(id) {
+                    found.insert(id.clone(), i);
+                }
+                claimed.insert(i);
+            }
+        }
+
+        let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_lowercase();
+        let ids: Vec<String> = self.notes.keys().cloned().collect();
+
+        // Recorded path, then content, then a unique file name
+        for pass in 0..3 {
+            for id in &ids {
+                if found.contains_key(id) {
+                    continue;
+                }
+                let entry = &self.notes[id];
+                let matches = |i: usize| match pass {
+                    0 => notes[i].0 == entry.path,
+                    1 => content_hash(&notes[i].1) == entry.hash,
+                    _ => file_name(&notes[i].0) == file_name(&entry.path),
+                };
+                let candidates: Vec<usize> = (0..notes.len())
+                    .filter(|i| !claimed.contains(i) && matches(*i))
+                    .collect();
+                if let [i] = candidates[..] {
+                    found.insert(id.clone(), i);
+                    claimed.insert(i);
+                }
+            }
+        }
+
+        for (id, i) in found {
+            if let Some(entry) = self.notes.get_mut(&id) {
+                entry.path = notes[i].0.clone();
+                entry.hash = content_hash(&notes[i].1);
+            }
+        }
+    }
+}
+
+/// IDs for the given notes, assigning new ones in the sidecar index as needed.
+/// Paths outside the vault are returned unchanged.
+pub(crate) fn ids_for_paths(vault_path: &str, paths: &[String]) -> Result<Vec<String>, String> {
+    let vault = PathBuf::from(vault_path);
+    let mut index = NoteIds::load(&vault);
+    let mut changed = false;
+    let mut ids = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let note = PathBuf::from(path);
+        let content = match fs::read_to_string(&note) {
+            Ok(c) if note.starts_with(&vault) => c,
+            _ => {
+                ids.push(path.clone());
+                continue;
+            }
+        };
+        let relative_path = relative(&vault, &note);
+        let hash = content_hash(&content);
+
+        let id = match frontmatter_id(&content).or_else(|| index.id_at(&relative_path)) {
+            Some(id) => id,
+            None => unique_id(12, &relative_path, |id| index.notes.contains_key(id)),
+        };
+        let entry = IdEntry {
+            path: relative_path,
+            hash,
+        };
+        if index
+            .notes
+            .get(&id)
+            .is_none_or(|e| e.path != entry.path || e.hash != hash)
+        {
+            // A frontmatter ID replaces one the index gave the note earlier
+            index
+                .notes
+                .retain(|other, e| *other == id || e.path != entry.path);
+            index.notes.insert(id.clone(), entry);
+            changed = true;
+        }
+        ids.push(id);
+    }
+
+    if changed {
+        index.save(&vault)?;
+    }
+    Ok(ids)
+}
+
+/// Current paths of the given notes. Unknown IDs whose notes are gone are
+/// dropped; values that are paths of existing notes (e.g., session state
+/// saved before IDs existed) are kept as they are.
+pub(crate) fn paths_for_ids(vault_path: &str, ids: &[String]) -> Vec<String> {
+    let vault = PathBuf::from(vault_path);
+    let mut index = NoteIds::load(&vault);
+
+    // Only search the vault when a note is not where the index last saw it
+    let still_there = |index: &NoteIds, id: &str| {
+        index.notes.get(id).is_some_and(|entry| {
+            fs::read_to_string(vault.join(&entry.path))
+                .is_ok_and(|c| frontmatter_id(&c).is_none_or(|own| own == id))
+        })
+    };
+    if ids
+        .iter()
+        .any(|id| index.notes.contains_key(id) && !still_there(&index, id))
+    {
+        index.reconcile(&vault);
+        let _ = index.save(&vault);
+    }
+
+    ids.iter()
+        .filter_map(|id| match index.notes.get(id) {
+            Some(entry) => Some(vault.join(&entry.path))
+                .filter(|p| p.exists())
+                .map(|p| p.to_string_lossy().to_string()),
+            None => Some(id.clone()).filter(|p| Path::new(p).exists()),
+        })
+        .collect()
+}
+
+/// Get a note's stable ID, assigning one if it has none - Tauri command
+#[tauri::command]
+pub fn get_note_id(vault_path: String, path: String) -> FsResult<String> {
+    if !Path::new(&path).starts_with(&vault_path) {
+        return FsResult::err("Note is not in the vault");
+    }
+    if !Path::new(&path).exists() {
+        return FsResult::err("Note not found");
+    }
+
+    match ids_for_paths(&vault_path, &[path]) {
+        Ok(ids) => FsResult::ok(ids.into_iter().next().unwrap_or_default()),
+        Err(e) => FsResult::err(&format!("Failed to save note IDs: {}", e)),
+    }
+}
+
+/// Find the current path of a note by its stable ID - Tauri command
+#[tauri::command]
+pub fn find_note_by_id(vault_path: String, id: String) -> FsResult<Option<String>> {
+    let vault = PathBuf::from(&vault_path);
+    let mut index = NoteIds::load(&vault);
+
+    // Notes with a frontmatter `id` need not be in the index
+    if !index.notes.contains_key(&id) {
+        let path = collect_notes(&vault).into_iter().find(|p| {
+            fs::read_to_string(p).is_ok_and(|c| frontmatter_id(&c).as_deref() == Some(id.as_str()))
+        });
+        return FsResult::ok(path.map(|p| p.to_string_lossy().to_string()));
+    }
+
+    index.reconcile(&vault);
+    if let Err(e) = index.save(&vault) {
+        return FsResult::err(&format!("Failed to save note IDs: {}", e));
+    }
+    let path = index
+        .notes
+        .get(&id)
+        .map(|entry| vault.join(&entry.path))
+        .filter(|p| p.exists());
+    FsResult::ok(path.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Point a note's sidecar ID at its new path after the app moved it
+pub(crate) fn record_move(old_path: &Path, new_path: &Path) {
+    // The vault is the nearest folder that has an ID index
+    let vault = match old_path.ancestors().find(|dir| ids_path(dir).exists()) {
+        Some(vault) => vault.to_path_buf(),
+        None => return,
+    };
+
+    let mut index = NoteIds::load(&vault);
+    let old_relative = relative(&vault, old_path);
+    if let Some(id) = index.id_at(&old_relative) {
+        if let Some(entry) = index.notes.get_mut(&id) {
+            entry.path = relative(&vault, new_path);
+        }
+        let _ = index.save(&vault);
+    }
+}
@@ -0,0 +1,198 @@
+//! Document outline (the heading tree) and generated tables of contents
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::headings::{heading_slug, parse_headings, Heading};
+use crate::history::{vault_for, Journal};
+use crate::links::{format_wiki_link, line_at};
+use crate::{fenced_line_mask, frontmatter, FsResult};
+
+/// Lines that open and close a generated table of contents
+const TOC_START: &str = "<!-- toc -->";
+const TOC_END: &str = "<!-- /toc -->";
+
+/// A heading with the headings nested under it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutlineItem {
+    #[serde(flatten)]
+    pub heading: Heading,
+    pub children: Vec<OutlineItem>,
+}
+
+/// Nest headings under the closest preceding heading of a lower level
+fn build_outline(headings: Vec<Heading>) -> Vec<OutlineItem> {
+    fn insert(items: &mut Vec<OutlineItem>, item: OutlineItem) {
+        match items.last_mut() {
+            Some(last) if last.heading.level < item.heading.level => {
+                insert(&mut last.children, item)
+            }
+            _ => items.push(item),
+        }
+    }
+
+    let mut outline = Vec::new();
+    for heading in headings {
+        insert(
+            &mut outline,
+            OutlineItem {
+                heading,
+                children: Vec::new(),
+            },
+        );
+    }
+    outline
+}
+
+/// Get the heading tree of a note - Tauri command
+///
+/// Covers ATX and setext headings; `#` lines in code blocks and frontmatter
+/// are not headings. Slugs are the ones `[[note#heading]]` links match.
+#[tauri::command]
+pub fn get_outline(path: String) -> FsResult<Vec<OutlineItem>> {
+    match fs::read_to_string(&path) {
+        Ok(content) => FsResult::ok(build_outline(parse_headings(&content))),
+        Err(e) => FsResult::err(&format!("Failed to read note: {}", e)),
+    }
+}
+
+/// Byte range of an existing TOC block, from its start marker through the
+/// end of its end marker line. Markers inside code blocks don't count.
+fn find_toc(content: &str) -> Option<(usize, usize)> {
+    let in_code = fenced_line_mask(content);
+    let mut start = None;
+    let mut offset = 0;
+
+    for (i, line) in content.split('\n').enumerate() {
+        let line_start = offset;
+        offset += line.len() + 1;
+        if in_code.get(i).copied().unwrap_or(false) {
+            continue;
+        }
+        match (start, line.trim()) {
+            (None, TOC_START) => start = Some(line_start),
+            (Some(start), TOC_END) => {
+                let end = line_start + line.trim_end_matches('\r').len();
+                return Some((start, end));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The TOC list: one `[[note#heading|heading]]` link per heading, indented by level.
+/// Naming the note keeps the links visible to link parsing, so renaming a
+/// heading updates them.
+fn toc_block(note_name: &str, headings: &[&Heading]) -> String {
+    let top = headings.iter().map(|h| h.level).min().unwrap_or(1);
+    let mut block = format!("{}\n", TOC_START);
+
+    for heading in headings {
+        let display: String = heading
+            .text
+            .chars()
+            .filter(|c| !matches!(c, '|' | '[' | ']'))
+            .collect();
+        // Repeated headings and text that can't go in a link use the slug
+        let plain = heading_slug(&heading.text) == heading.slug
+            && display == heading.text
+            && !heading.text.contains('#');
+        let anchor = if plain { &heading.text } else { &heading.slug };
+        let link = format_wiki_link(note_name, Some(anchor), Some(display.trim()));
+        block.push_str(&format!("{}- {}\n", "  ".repeat(heading.level - top), link));
+    }
+
+    block.push_str(TOC_END);
+    block
+}
+
+/// Result of generating a table of contents
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TocUpdate {
+    /// Whether the note had no TOC block before
+    pub created: bool,
+    /// Number of headings listed
+    pub entries: usize,
+    /// Line of the TOC start marker (1-based)
+    pub line: usize,
+}
+
+/// Insert or refresh a generated table of contents in a note - Tauri command
+///
+/// The TOC lives between `<!-- toc -->` and `<!-- /toc -->` lines and lists
+/// headings from `min_level` to `max_level` (default 1 to 6) as links into
+/// the note. An existing block is replaced in place; otherwise the block goes
+/// after the frontmatter and a leading title heading.
+#[tauri::command]
+pub fn update_toc(
+    path: String,
+    min_level: Option<usize>,
+    max_level: Option<usize>,
+) -> FsResult<TocUpdate> {
+    let note_path = PathBuf::from(&path);
+    let min_level = min_level.unwrap_or(1).clamp(1, 6);
+    let max_level = max_level.unwrap_or(6).clamp(min_level, 6);
+
+    let content = match fs::read_to_string(&note_path) {
+        Ok(c) => c,
+        Err(e) => return FsResult::err(&format!("Failed to read note: {}", e)),
+    };
+
+    let existing = find_toc(&content);
+    let all_headings = parse_headings(&content);
+    let headings: Vec<&Heading> = all_headings
+        .iter()
+        .filter(|h| (min_level..=max_level).contains(&h.level) && !h.slug.is_empty())
+        .filter(|h| existing.is_none_or(|(start, end)| h.start < start || h.start > end))
+        .collect();
+    let note_name = note_path.file_stem().unwrap_or_default().to_string_lossy();
+    let block = toc_block(&note_name, &headings);
+
+    let (updated, block_start) = match existing {
+        Some((start, end)) => (
+            format!("{}{}{}", &content[..start], block, &content[end..]),
+            start,
+        ),
+        None => {
+            let body_start = frontmatter::parse_frontmatter(&content).map_or(0, |fm| fm.body_start);
+            // After a title heading that opens the note
+            let at = match all_headings.first() {
+                Some(h) if h.level == 1 && content[body_start..h.start].trim().is_empty() => {
+                    content[h.end..]
+                        .find('\n')
+                        .map_or(content.len(), |i| h.end + i + 1)
+                }
+                _ => body_start,
+            };
+
+            let before = &content[..at];
+            let after = content[at..].trim_start_matches(['\r', '\n']);
+            let lead = match before {
+                "" => "",
+                b if b.ends_with("\n\n") || b.ends_with("\r\n\r\n") => "",
+                b if b.ends_with('\n') => "\n",
+                _ => "\n\n",
+            };
+            let trail = if after.is_empty() { "\n" } else { "\n\n" };
+            (
+                format!("{}{}{}{}{}", before, lead, block, trail, after),
+                at + lead.len(),
+            )
+        }
+    };
+
+    if updated != content {
+        let journal = Journal::new(vault_for(None, &note_path), "Update table of contents");
+        if let Err(e) = journal.finish_write(&note_path, &content, &updated) {
+            return FsResult::err(&e);
+        }
+    }
+
+    FsResult::ok(TocUpdate {
+        created: existing.is_none(),
+        entries: headings.len(),
+        line: line_at(&updated, block_start),
+    })
+}
@@ -0,0 +1,482 @@
+//! Daily, weekly and monthly periodic notes
+//!
+//! Each period has its own folder, moment.js-style filename format (which may
+//! contain `/` to nest notes, e.g. "YYYY/MM/YYYY-MM-DD") and optional template.
+//! Settings are stored per vault in the app configuration.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::templates::{find_template, format_moment, render_note_body, TemplateContext};
+use crate::{collect_vault_files, save_config, write_note, AppState, FsResult, Note};
+
+/// Kind of periodic note
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodKind {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Direction for navigating between periodic notes
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NavDirection {
+    Previous,
+    Next,
+}
+
+/// Settings for one kind of periodic note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodSettings {
+    /// Folder relative to the vault root ("" for the root)
+    pub folder: String,
+    /// moment.js-style filename format
+    pub format: String,
+    /// Template name in the vault's templates folder
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// Periodic note settings for a vault
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodicNotesConfig {
+    #[serde(default = "default_daily")]
+    pub daily: PeriodSettings,
+    #[serde(default = "default_weekly")]
+    pub weekly: PeriodSettings,
+    #[serde(default = "default_monthly")]
+    pub monthly: PeriodSettings,
+}
+
+fn default_daily() -> PeriodSettings {
+    PeriodSettings {
+        folder: "Daily".to_string(),
+        format: "YYYY-MM-DD".to_string(),
+        template: None,
+    }
+}
+fn default_weekly() -> PeriodSettings {
+    PeriodSettings {
+        folder: "Weekly".to_string(),
+        format: "GGGG-[W]WW".to_string(),
+        template: None,
+    }
+}
+fn default_monthly() -> PeriodSettings {
+    PeriodSettings {
+        folder: "Monthly".to_string(),
+        format: "YYYY-MM".to_string(),
+        template: None,
+    }
+}
+
+impl Default for PeriodicNotesConfig {
+    fn default() -> Self {
+        PeriodicNotesConfig {
+            daily: default_daily(),
+            weekly: default_weekly(),
+            monthly: default_monthly(),
+        }
+    }
+}
+
+impl PeriodicNotesConfig {
+    fn settings(&self, kind: PeriodKind) -> &PeriodSettings {
+        match kind {
+            PeriodKind::Daily => &self.daily,
+            PeriodKind::Weekly => &self.weekly,
+            PeriodKind::Monthly => &self.monthly,
+        }
+    }
+}
+
+/// A periodic note that was opened or created
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodicNote {
+    pub note: Note,
+    /// Start date of the period (YYYY-MM-DD)
+    pub date: String,
+    /// Whether the note was created by this call
+    pub created: bool,
+    /// Byte offset of the template's `{{cursor}}` placeholder, if created
+    pub cursor: Option<usize>,
+}
+
+/// An existing periodic note on disk
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodicNoteEntry {
+    /// Start date of the period (YYYY-MM-DD)
+    pub date: String,
+    pub name: String,
+    pub path: String,
+    /// File size in bytes, useful for heatmap intensity
+    pub size: u64,
+}
+
+/// First day of the period containing `date`
+fn period_start(kind: PeriodKind, date: NaiveDate) -> NaiveDate {
+    match kind {
+        PeriodKind::Daily => date,
+        PeriodKind::Weekly => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        PeriodKind::Monthly => date.with_day(1).unwrap_or(date),
+    }
+}
+
+/// A local date-time for a calendar date, taken at noon so DST gaps never hit
+fn local_datetime(date: NaiveDate) -> DateTime<Local> {
+    date.and_hms_opt(12, 0, 0)
+        .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+        .unwrap_or_else(Local::now)
+}
+
+/// Parse a date argument: "YYYY-MM-DD", an RFC 3339 timestamp (converted to
+/// the local calendar date), or today when absent
+fn parse_date_arg(date: Option<&str>) -> Result<NaiveDate, String> {
+    let date = match date.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => d,
+        None => return Ok(Local::now().date_naive()),
+    };
+
+    if let Ok(d) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(d);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(date) {
+        return Ok(dt.with_timezone(&Local).date_naive());
+    }
+    Err(format!("Invalid date: {}", date))
+}
+
+/// Build a regex that matches file names produced by a moment.js-style format,
+/// capturing the date parts
+fn format_regex(format: &str) -> Option<Regex> {
+    // (token, capture group, pattern) - longest tokens first
+    const TOKENS: [(&str, &str, &str); 22] = [
+        ("YYYY", "Y", r"\d{4}"),
+        ("GGGG", "G", r"\d{4}"),
+        ("gggg", "G", r"\d{4}"),
+        ("MMMM", "N", r"[^\W\d_]+"),
+        ("dddd", "", r"[^\W\d_]+"),
+        ("DDDD", "J", r"\d{3}"),
+        ("MMM", "N", r"[^\W\d_]+"),
+        ("ddd", "", r"[^\W\d_]+"),
+        ("YY", "", r"\d{2}"),
+        ("MM", "M", r"\d{2}"),
+        ("DD", "D", r"\d{2}"),
+        ("WW", "W", r"\d{2}"),
+        ("ww", "W", r"\d{2}"),
+        ("HH", "", r"\d{2}"),
+        ("hh", "", r"\d{2}"),
+        ("mm", "", r"\d{2}"),
+        ("ss", "", r"\d{2}"),
+        ("M", "M", r"\d{1,2}"),
+        ("D", "D", r"\d{1,2}"),
+        ("W", "W", r"\d{1,2}"),
+        ("w", "W", r"\d{1,2}"),
+        ("A", "", r"[AaPp][Mm]"),
+    ];
+
+    let mut pattern = String::from("^");
+    let mut used: Vec<&str> = Vec::new();
+    let mut rest = format;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(close) = rest.find(']') {
+                pattern.push_str(&regex::escape(&rest[1..close]));
+                rest = &rest[close + 1..];
+                continue;
+            }
+        }
+
+        for (token, group, part) in TOKENS {
+            if let Some(after) = rest.strip_prefix(token) {
+                // Repeated tokens (e.g., "YYYY/YYYY-MM-DD") capture only once
+                if group.is_empty() || used.contains(&group) {
+                    pattern.push_str(&format!("(?:{})", part));
+                } else {
+                    pattern.push_str(&format!("(?P<{}>{})", group, part));
+                    used.push(group);
+                }
+                rest = after;
+                continue 'outer;
+            }
+        }
+
+        pattern.push_str(&regex::escape(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+/// Work out the period a note belongs to from its path relative to the
+/// period folder (without .md)
+fn date_from_name(kind: PeriodKind, format: &str, re: &Regex, name: &str) -> Option<NaiveDate> {
+    let cap = re.captures(name)?;
+    let num = |g: &str| cap.name(g).and_then(|m| m.as_str().parse::<u32>().ok());
+
+    let month = num("M").or_else(|| {
+        let month_name = cap.name("N")?.as_str();
+        (1..=12).find(|m| {
+            let d = NaiveDate::from_ymd_opt(2000, *m, 1).unwrap();
+            d.format("%B").to_string().eq_ignore_ascii_case(month_name)
+                || d.format("%b").to_string().eq_ignore_ascii_case(month_name)
+        })
+    });
+    let year = num("Y").map(|y| y as i32);
+
+    let date = if let (Some(y), Some(m), Some(d)) = (year, month, num("D")) {
+        NaiveDate::from_ymd_opt(y, m, d)
+    } else if let (Some(y), Some(w)) = (num("G").map(|y| y as i32).or(year), num("W")) {
+        NaiveDate::from_isoywd_opt(y, w, Weekday::Mon)
+    } else if let (Some(y), Some(m)) = (year, month) {
+        NaiveDate::from_ymd_opt(y, m, 1)
+    } else if let (Some(y), Some(j)) = (year, num("J")) {
+        NaiveDate::from_yo_opt(y, j)
+    } else {
+        None
+    }?;
+
+    // Only accept names that round-trip, so "2026-02-31" or a mismatched
+    // weekday name is not mistaken for a periodic note
+    let start = period_start(kind, date);
+    if format_moment(&local_datetime(date), format) == name
+        || format_moment(&local_datetime(start), format) == name
+    {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Scan a period folder for existing notes, sorted by date
+fn list_periodic_notes(
+    vault: &Path,
+    kind: PeriodKind,
+    settings: &PeriodSettings,
+) -> Vec<PeriodicNoteEntry> {
+    let folder = vault.join(settings.folder.trim_matches('/'));
+    let re = match format_regex(&settings.format) {
+        Some(re) => re,
+        None => return vec![],
+    };
+
+    let mut entries: Vec<PeriodicNoteEntry> = collect_vault_files(&folder)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|p| {
+            let relative = p
+                .strip_prefix(&folder)
+                .ok()?
+                .with_extension("")
+                .to_string_lossy()
+                .replace('\\', "/");
+            let date = date_from_name(kind, &settings.format, &re, &relative)?;
+            Some(PeriodicNoteEntry {
+                date: date.format("%Y-%m-%d").to_string(),
+                name: p.file_stem()?.to_string_lossy().to_string(),
+                size: fs::metadata(&p).map(|m| m.len()).unwrap_or(0),
+                path: p.to_string_lossy().to_string(),
+            })
+        })
+        .collect();
+
+    // ISO dates sort chronologically as strings
+    entries.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+    entries
+}
+
+/// Get the periodic notes configuration for a vault
+fn vault_config(app_handle: &tauri::AppHandle, vault_path: &str) -> PeriodicNotesConfig {
+    let state = app_handle.state::<AppState>();
+    let configs = state.periodic_notes_per_vault.lock();
+    configs
+        .ok()
+        .and_then(|c| c.get(vault_path).cloned())
+        .unwrap_or_default()
+}
+
+/// Get periodic notes settings for a vault - Tauri command
+#[tauri::command]
+pub fn get_periodic_notes_config(
+    app_handle: tauri::AppHandle,
+    vault_path: String,
+) -> FsResult<PeriodicNotesConfig> {
+    FsResult::ok(vault_config(&app_handle, &vault_path))
+}
+
+/// Save periodic notes settings for a vault - Tauri command
+#[tauri::command]
+pub fn set_periodic_notes_config(
+    app_handle: tauri::AppHandle,
+    vault_path: String,
+    config: PeriodicNotesConfig,
+) -> FsResult<()> {
+    for kind in [PeriodKind::Daily, PeriodKind::Weekly, PeriodKind::Monthly] {
+        if config.settings(kind).format.trim().is_empty() {
+            return FsResult::err("Filename format cannot be empty");
+        }
+    }
+
+    let state = app_handle.state::<AppState>();
+    if let Ok(mut configs) = state.periodic_notes_per_vault.lock() {
+        configs.insert(vault_path, config);
+    }
+
+    // Save configuration
+    let _ = save_config(&app_handle);
+
+    FsResult::ok(())
+}
+
+/// Open the periodic note for a date, creating it from its template if
+/// needed - Tauri command
+#[tauri::command]
+pub fn open_or_create_periodic_note(
+    app_handle: tauri::AppHandle,
+    vault_path: String,
+    kind: PeriodKind,
+    date: Option<String>,
+) -> FsResult<PeriodicNote> {
+    let vault = PathBuf::from(&vault_path);
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+
+    let date = match parse_date_arg(date.as_deref()) {
+        Ok(d) => period_start(kind, d),
+        Err(e) => return FsResult::err(&e),
+    };
+    let config = vault_config(&app_handle, &vault_path);
+    let settings = config.settings(kind);
+    let date_str = date.format("%Y-%m-%d").to_string();
+
+    // The format may contain '/' to nest notes in sub-folders
+    let relative = format_moment(&local_datetime(date), &settings.format);
+    let mut parts: Vec<&str> = settings
+        .folder
+        .split('/')
+        .chain(relative.split('/'))
+        .filter(|p| !p.is_empty())
+        .collect();
+    let name = match parts.pop() {
+        Some(n) => n.to_string(),
+        None => return FsResult::err("Filename format produced an empty name"),
+    };
+    let folder = if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    };
+
+    let path = match &folder {
+        Some(f) => vault.join(f),
+        None => vault.clone(),
+    }
+    .join(format!("{}.md", name));
+
+    if path.exists() {
+        let result = crate::read_note(path.to_string_lossy().to_string());
+        return match result.data {
+            Some(note) => FsResult::ok(PeriodicNote {
+                note,
+                date: date_str,
+                created: false,
+                cursor: None,
+            }),
+            None => FsResult::err(result.error.as_deref().unwrap_or("Failed to read note")),
+        };
+    }
+
+    let (content, cursor) = match settings.template.as_deref().filter(|t| !t.is_empty()) {
+        Some(template) => {
+            let template_path = match find_template(&vault, template) {
+                Some(p) => p,
+                None => return FsResult::err("Template not found"),
+            };
+            let raw = match fs::read_to_string(&template_path) {
+                Ok(c) => c,
+                Err(e) => return FsResult::err(&format!("Failed to read template: {}", e)),
+            };
+            let vars = HashMap::new();
+            let ctx = TemplateContext {
+                title: &name,
+                now: local_datetime(date),
+                vars: &vars,
+            };
+            render_note_body(&raw, &ctx)
+        }
+        None => (String::new(), None),
+    };
+
+    let result = write_note(vault_path, name, content, folder);
+    match result.data {
+        Some(note) => FsResult::ok(PeriodicNote {
+            note,
+            date: date_str,
+            created: true,
+            cursor,
+        }),
+        None => FsResult::err(result.error.as_deref().unwrap_or("Failed to write note")),
+    }
+}
+
+/// Find the nearest existing periodic note before or after a date, skipping
+/// gaps - Tauri command
+#[tauri::command]
+pub fn get_adjacent_periodic_note(
+    app_handle: tauri::AppHandle,
+    vault_path: String,
+    kind: PeriodKind,
+    date: String,
+    direction: NavDirection,
+) -> FsResult<Option<PeriodicNoteEntry>> {
+    let date = match parse_date_arg(Some(&date)) {
+        Ok(d) => period_start(kind, d).format("%Y-%m-%d").to_string(),
+        Err(e) => return FsResult::err(&e),
+    };
+    let config = vault_config(&app_handle, &vault_path);
+    let entries = list_periodic_notes(&PathBuf::from(&vault_path), kind, config.settings(kind));
+
+    let found = match direction {
+        NavDirection::Previous => entries.into_iter().rev().find(|e| e.date < date),
+        NavDirection::Next => entries.into_iter().find(|e| e.date > date),
+    };
+
+    FsResult::ok(found)
+}
+
+/// List existing periodic notes between two dates (inclusive), for calendar
+/// and heatmap views - Tauri command
+#[tauri::command]
+pub fn get_periodic_note_calendar(
+    app_handle: tauri::AppHandle,
+    vault_path: String,
+    kind: PeriodKind,
+    start: String,
+    end: String,
+) -> FsResult<Vec<PeriodicNoteEntry>> {
+    let (start, end) = match (parse_date_arg(Some(&start)), parse_date_arg(Some(&end))) {
+        (Ok(s), Ok(e)) => (
+            period_start(kind, s).format("%Y-%m-%d").to_string(),
+            e.format("%Y-%m-%d").to_string(),
+        ),
+        (Err(e), _) | (_, Err(e)) => return FsResult::err(&e),
+    };
+    let config = vault_config(&app_handle, &vault_path);
+
+    let entries = list_periodic_notes(&PathBuf::from(&vault_path), kind, config.settings(kind))
+        .into_iter()
+        .filter(|e| e.date >= start && e.date <= end)
+        .collect();
+
+    FsResult::ok(entries)
+}
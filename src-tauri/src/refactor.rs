@@ -0,0 +1,704 @@
+//! Refactoring notes: splitting a note at its headings, extracting a
+//! selection into a new note and merging notes
+//!
+//! Each command plans where every piece of the original text ends up, then
+//! rewrites links into that text (including `#heading` and `#^block` links)
+//! to follow it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::blocks::{find_block, parse_blocks, Block};
+use crate::headings::{find_heading, parse_headings, section_range, Heading};
+use crate::history::Journal;
+use crate::links::{
+    encode_link_path, format_wiki_link, relative_link_path, rewrite_links, LinkRewrite,
+};
+use crate::templates::sanitize_file_name;
+use crate::{frontmatter, parse_note_links, FsResult, NoteIndex, WikiLink};
+
+/// Result of splitting, extracting or merging notes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefactorResult {
+    /// Notes created, in order
+    pub created: Vec<String>,
+    /// Notes deleted because all of their content moved
+    pub deleted: Vec<String>,
+    /// Links rewritten to follow the moved text
+    pub links: LinkRewrite,
+}
+
+/// Text copied unchanged from an original note into a resulting note
+struct Piece {
+    old: Range<usize>,
+    dest: PathBuf,
+    new_start: usize,
+}
+
+/// An original note and where its text went
+struct Relocation {
+    note: usize,
+    content: String,
+    headings: Vec<Heading>,
+    blocks: Vec<Block>,
+    pieces: Vec<Piece>,
+    /// Headings (by offset) that became notes of their own
+    titles: Vec<(usize, PathBuf)>,
+    /// Where links to the whole note go, and the heading there (by offset)
+    /// they point at
+    whole: (PathBuf, Option<usize>),
+}
+
+impl Relocation {
+    /// The note an offset of the original text moved to, and its offset there
+    fn moved(&self, offset: usize) -> Option<(PathBuf, usize)> {
+        self.pieces
+            .iter()
+            .find(|p| p.old.contains(&offset))
+            .map(|p| (p.dest.clone(), p.new_start + offset - p.old.start))
+    }
+}
+
+/// What a rewritten link points at within its note
+enum Anchor {
+    None,
+    /// The heading starting at this offset
+    Heading(usize),
+    Block(String),
+}
+
+/// The new content of the notes a refactor creates or changes
+struct Plan<'a> {
+    index: &'a NoteIndex,
+    relocations: Vec<Relocation>,
+    outputs: Vec<(PathBuf, String)>,
+    removed: Vec<PathBuf>,
+}
+
+impl<'a> Plan<'a> {
+    fn new(index: &'a NoteIndex) -> Plan<'a> {
+        Plan {
+            index,
+            relocations: Vec::new(),
+            outputs: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Start tracking the text of an existing note
+    fn relocate(&mut self, path: &Path) -> Result<usize, String> {
+        let note = self.index.position(path).ok_or("Note not found")?;
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read note: {}", e))?;
+
+        self.relocations.push(Relocation {
+            note,
+            headings: parse_headings(&content),
+            blocks: parse_blocks(&content),
+            content,
+            pieces: Vec::new(),
+            titles: Vec::new(),
+            whole: (path.to_path_buf(), None),
+        });
+        Ok(self.relocations.len() - 1)
+    }
+
+    fn output(&mut self, dest: &Path) -> &mut String {
+        let i = match self.outputs.iter().position(|(p, _)| p == dest) {
+            Some(i) => i,
+            None => {
+                self.outputs.push((dest.to_path_buf(), String::new()));
+                self.outputs.len() - 1
+            }
+        };
+        &mut self.outputs[i].1
+    }
+
+    /// Append part of an original note to `dest`
+    fn copy(&mut self, relocation: usize, old: Range<usize>, dest: &Path) {
+        let text = self.relocations[relocation].content[old.clone()].to_string();
+        let output = self.output(dest);
+        let new_start = output.len();
+        output.push_str(&text);
+        self.relocations[relocation].pieces.push(Piece {
+            old,
+            dest: dest.to_path_buf(),
+            new_start,
+        });
+    }
+
+    /// Append generated text to `dest` as a paragraph of its own; returns
+    /// its offset
+    fn paragraph(&mut self, dest: &Path, text: &str) -> usize {
+        let output = self.output(dest);
+        if !output.is_empty() {
+            let trimmed = output.trim_end().len();
+            output.truncate(trimmed);
+            output.push_str("\n\n");
+
+            // Copied text that was trimmed is no longer in the output
+            for piece in self.relocations.iter_mut().flat_map(|r| &mut r.pieces) {
+                if piece.dest == dest && piece.new_start + piece.old.len() > trimmed {
+                    piece.old.end = piece.old.start + trimmed.saturating_sub(piece.new_start);
+                }
+            }
+        }
+        let output = self.output(dest);
+        let start = output.len();
+        output.push_str(text);
+        output.push_str("\n\n");
+        start
+    }
+
+    /// A path for a new note named after `title` in `folder`, avoiding
+    /// names used anywhere in the vault
+    fn new_note_path(&self, folder: &Path, title: &str) -> PathBuf {
+        let name = note_name(title);
+        (0..)
+            .map(|i| match i {
+                0 => name.clone(),
+                i => format!("{} {}", name, i),
+            })
+            .find(|n| {
+                let path = folder.join(format!("{}.md", n));
+                !path.exists()
+                    && self.index.named(n).is_empty()
+                    && !self.outputs.iter().any(|(p, _)| {
+                        p.to_string_lossy().to_lowercase() == path.to_string_lossy().to_lowercase()
+                    })
+            })
+            .map(|n| folder.join(format!("{}.md", n)))
+            .unwrap_or_default()
+    }
+
+    /// Link text that leads to `dest` from anywhere in the vault
+    fn link_target(&self, dest: &Path) -> String {
+        if let Some(i) = self.index.position(dest) {
+            return self.index.link_target(i);
+        }
+        let name = dest
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let shared = self
+            .index
+            .named(&name)
+            .iter()
+            .any(|i| !self.removed.contains(&self.index.notes[*i]));
+        if !shared {
+            return name;
+        }
+        let relative = dest
+            .strip_prefix(&self.index.vault)
+            .unwrap_or(dest)
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/");
+        match relative.contains('/') {
+            true => relative,
+            false => format!("/{}", relative),
+        }
+    }
+
+    /// The original note whose text contains `offset` of a resulting note;
+    /// `None` for generated text
+    fn origin(&self, dest: &Path, offset: usize) -> Option<&Path> {
+        self.relocations
+            .iter()
+            .find(|r| {
+                r.pieces.iter().any(|p| {
+                    p.dest == dest && p.new_start <= offset && offset < p.new_start + p.old.len()
+                })
+            })
+            .map(|r| self.index.notes[r.note].as_path())
+    }
+
+    /// Where a link into a relocated note now points; `None` leaves it alone
+    fn destination(
+        &self,
+        r: &Relocation,
+        link: &WikiLink,
+        headings: &HashMap<PathBuf, Vec<Heading>>,
+    ) -> Option<(PathBuf, Anchor)> {
+        let whole = || {
+            let (dest, heading) = &r.whole;
+            (dest.clone(), heading.map_or(Anchor::None, Anchor::Heading))
+        };
+
+        let anchored = if let Some(reference) = &link.heading {
+            find_heading(&r.headings, reference).and_then(|h| {
+                match r.titles.iter().find(|(start, _)| *start == h.start) {
+                    Some((_, dest)) => Some((dest.clone(), Anchor::None)),
+                    None => r
+                        .moved(h.start)
+                        .map(|(dest, at)| (dest, Anchor::Heading(at))),
+                }
+            })
+        } else if let Some(id) = &link.block_id {
+            find_block(&r.blocks, id).and_then(|b| {
+                r.moved(b.start)
+                    .map(|(dest, _)| (dest, Anchor::Block(b.id.clone())))
+            })
+        } else {
+            return Some(whole());
+        };
+
+        // Links to a missing heading or block only change when the note is
+        // gone; they then keep their anchor if the note they lead to has it
+        if anchored.is_some() || !self.removed.contains(&self.index.notes[r.note]) {
+            return anchored;
+        }
+        let (dest, _) = whole();
+        let same = match (&link.heading, &link.block_id) {
+            (Some(reference), _) => headings
+                .get(&dest)
+                .and_then(|headings| find_heading(headings, reference))
+                .map(|h| Anchor::Heading(h.start)),
+            (None, Some(id)) => self
+                .outputs
+                .iter()
+                .find(|(p, _)| *p == dest)
+                .and_then(|(_, content)| {
+                    find_block(&parse_blocks(content), id).map(|b| b.id.clone())
+                })
+                .map(Anchor::Block),
+            (None, None) => None,
+        };
+        Some(match same {
+            Some(anchor) => (dest, anchor),
+            None => whole(),
+        })
+    }
+
+    /// The `#` part of a rewritten link: heading text when it picks the
+    /// heading unambiguously, else its slug
+    fn anchor_text(
+        &self,
+        dest: &Path,
+        anchor: &Anchor,
+        headings: &HashMap<PathBuf, Vec<Heading>>,
+    ) -> Option<String> {
+        match anchor {
+            Anchor::None => None,
+            Anchor::Block(id) => Some(format!("^{}", id)),
+            Anchor::Heading(at) => {
+                let headings = headings.get(dest)?;
+                let heading = headings.iter().find(|h| h.start == *at)?;
+                let plain = !heading.text.contains(['#', '|', '[', ']'])
+                    && find_heading(headings, &heading.text).is_some_and(|h| h.start == *at);
+                Some(match plain {
+                    true => heading.text.clone(),
+                    false => heading.slug.clone(),
+                })
+            }
+        }
+    }
+
+    /// Write a link to `dest` in the style of `link`, placed in `current`
+    fn format_link(
+        &self,
+        link: &WikiLink,
+        current: &Path,
+        dest: &Path,
+        anchor: Option<String>,
+        keep_target: bool,
+    ) -> String {
+        if !link.is_markdown {
+            let target = match keep_target {
+                true => link.target.clone(),
+                false => self.link_target(dest),
+            };
+            return format_wiki_link(&target, anchor.as_deref(), link.display_text.as_deref());
+        }
+
+        let dir = current.parent().unwrap_or(&self.index.vault);
+        let mut url = encode_link_path(&relative_link_path(dir, dest));
+        if let Some(anchor) = &anchor {
+            url.push('#');
+            url.push_str(&encode_link_path(anchor));
+        }
+        let text = link.display_text.clone().unwrap_or_else(|| {
+            dest.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        let bang = if link.is_embed { "!" } else { "" };
+        format!("{}[{}]({})", bang, text, url)
+    }
+
+    /// New text for a link written in `origin` that now sits in `current`
+    fn retarget(
+        &self,
+        link: &WikiLink,
+        origin: &Path,
+        current: &Path,
+        headings: &HashMap<PathBuf, Vec<Heading>>,
+    ) -> Option<String> {
+        let resolved = self.index.resolve_link(link, origin)?;
+        let note = &self.index.notes[resolved];
+        let same_folder = origin.parent() == current.parent();
+
+        let relocation = self.relocations.iter().find(|r| r.note == resolved);
+        let (dest, anchor) = match relocation {
+            Some(r) => self.destination(r, link, headings)?,
+            None => {
+                // Moved text may now resolve the same link differently
+                let unchanged = same_folder
+                    || (!link.is_markdown
+                        && self.index.resolve_from(&link.target, Some(current)) == Some(resolved));
+                if unchanged {
+                    return None;
+                }
+                return Some(self.format_link(link, current, note, link.anchor(), false));
+            }
+        };
+
+        let note_unchanged = dest == *note && same_folder && !self.removed.contains(note);
+        let anchor_unchanged = match &anchor {
+            Anchor::None => link.anchor().is_none(),
+            Anchor::Heading(at) => link
+                .heading
+                .as_deref()
+                .zip(headings.get(&dest))
+                .and_then(|(reference, headings)| find_heading(headings, reference))
+                .is_some_and(|h| h.start == *at),
+            Anchor::Block(_) => link.block_id.is_some(),
+        };
+        if note_unchanged && anchor_unchanged {
+            return None;
+        }
+
+        let anchor = self.anchor_text(&dest, &anchor, headings);
+        Some(self.format_link(link, current, &dest, anchor, note_unchanged))
+    }
+
+    /// Rewrite links across the vault and write every change as one
+    /// operation
+    fn apply(mut self, vault: &Path, label: String) -> FsResult<RefactorResult> {
+        for (path, content) in self.outputs.iter_mut() {
+            // Notes kept as they were are not rewritten
+            if fs::read_to_string(path.as_path()).is_ok_and(|c| c == *content) {
+                continue;
+            }
+            content.truncate(content.trim_end().len());
+            if !content.is_empty() {
+                content.push('\n');
+            }
+        }
+        let headings: HashMap<PathBuf, Vec<Heading>> = self
+            .outputs
+            .iter()
+            .map(|(path, content)| (path.clone(), parse_headings(content)))
+            .collect();
+
+        let mut links = LinkRewrite::default();
+        let mut writes = Vec::new();
+
+        for (path, content) in &self.outputs {
+            let (updated, count) = rewrite_links(content, parse_note_links(content), |link| {
+                let origin = self.origin(path, link.start)?;
+                self.retarget(link, origin, path, &headings)
+            });
+            if count > 0 {
+                links.changed_notes.push(path.to_string_lossy().to_string());
+                links.links_updated += count;
+            }
+            writes.push((path.clone(), fs::read_to_string(path).ok(), updated));
+        }
+
+        for path in &self.index.notes {
+            if self.removed.contains(path) || self.outputs.iter().any(|(p, _)| p == path) {
+                continue;
+            }
+            let content = match fs::read_to_string(path) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            let (updated, count) = rewrite_links(&content, parse_note_links(&content), |link| {
+                self.retarget(link, path, path, &headings)
+            });
+            if count == 0 {
+                continue;
+            }
+            links.changed_notes.push(path.to_string_lossy().to_string());
+            links.links_updated += count;
+            writes.push((path.clone(), Some(content), updated));
+        }
+
+        // New notes first, so a failure part way never leaves links to
+        // notes that don't exist
+        let mut journal = Journal::new(Some(vault.to_path_buf()), label);
+        let mut created = Vec::new();
+        writes.sort_by_key(|(_, before, _)| before.is_some());
+        for (path, before, after) in &writes {
+            let written = match before {
+                None => journal.create(path, after),
+                Some(before) if before != after => journal.write(path, before, after),
+                Some(_) => continue,
+            };
+            if let Err(e) = written {
+                return FsResult::err(&format!("Failed to write {}: {}", path.display(), e));
+            }
+            if before.is_none() {
+                created.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        let mut deleted = Vec::new();
+        for path in &self.removed {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            if let Err(e) = journal.remove(path, &content) {
+                return FsResult::err(&format!("Failed to delete {}: {}", path.display(), e));
+            }
+            deleted.push(path.to_string_lossy().to_string());
+        }
+
+        match journal.finish() {
+            Ok(id) => links.operation_id = id,
+            Err(e) => return FsResult::err(&format!("Applied, but failed to save history: {}", e)),
+        }
+        FsResult::ok(RefactorResult {
+            created,
+            deleted,
+            links,
+        })
+    }
+}
+
+/// A note name made from heading or user text
+fn note_name(text: &str) -> String {
+    let name: String = sanitize_file_name(text)
+        .chars()
+        .filter(|c| !matches!(c, '#' | '^' | '[' | ']'))
+        .collect();
+    let name = name.trim().trim_start_matches('.').trim();
+    match name {
+        "" => "Untitled".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// `range` without surrounding whitespace
+fn trimmed(content: &str, range: Range<usize>) -> Range<usize> {
+    let text = &content[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.start + text.trim_end().len();
+    start.min(end)..end
+}
+
+fn note_folder(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// Move each section at heading `level` into a note of its own - Tauri command
+///
+/// Each section (up to the next heading of the same or a higher level)
+/// becomes a note named after its heading, in the same folder, and is
+/// replaced by a `[[link]]` to it. Links to a split heading now lead to its
+/// note; links to the headings and blocks under it follow them there.
+#[tauri::command]
+pub fn split_note(vault_path: String, path: String, level: usize) -> FsResult<RefactorResult> {
+    let vault = PathBuf::from(&vault_path);
+    let note_path = PathBuf::from(&path);
+
+    if !(1..=6).contains(&level) {
+        return FsResult::err("Heading level must be between 1 and 6");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut plan = Plan::new(&index);
+    let source = match plan.relocate(&note_path) {
+        Ok(r) => r,
+        Err(e) => return FsResult::err(&e),
+    };
+    let content = plan.relocations[source].content.clone();
+    let headings = plan.relocations[source].headings.clone();
+
+    let sections: Vec<&Heading> = headings.iter().filter(|h| h.level == level).collect();
+    if sections.is_empty() {
+        return FsResult::err(&format!("The note has no level {} headings", level));
+    }
+
+    let folder = note_folder(&note_path);
+    let mut cursor = 0;
+    for heading in sections {
+        let (start, end) = section_range(&content, &headings, heading);
+        let dest = plan.new_note_path(&folder, &heading.text);
+
+        plan.copy(source, cursor..start, &note_path);
+        let link = format_wiki_link(&plan.link_target(&dest), None, None);
+        plan.paragraph(&note_path, &link);
+
+        plan.output(&dest);
+        plan.copy(source, trimmed(&content, heading.end..end), &dest);
+        plan.relocations[source].titles.push((heading.start, dest));
+        cursor = end;
+    }
+    plan.copy(source, cursor..content.len(), &note_path);
+
+    let label = format!("Split \"{}\"", index.name(plan.relocations[source].note));
+    plan.apply(&vault, label)
+}
+
+/// Move the text between byte offsets `start` and `end` of a note into a
+/// new note, leaving a `[[link]]` to it - Tauri command
+///
+/// The new note goes in the same folder. Links to headings and blocks in the
+/// selection are rewritten to point into the new note.
+#[tauri::command]
+pub fn extract_selection(
+    vault_path: String,
+    path: String,
+    start: usize,
+    end: usize,
+    new_name: String,
+) -> FsResult<RefactorResult> {
+    let vault = PathBuf::from(&vault_path);
+    let note_path = PathBuf::from(&path);
+
+    let index = NoteIndex::build(&vault);
+    let mut plan = Plan::new(&index);
+    let source = match plan.relocate(&note_path) {
+        Ok(r) => r,
+        Err(e) => return FsResult::err(&e),
+    };
+    let content = plan.relocations[source].content.clone();
+
+    let in_range = start < end
+        && end <= content.len()
+        && content.is_char_boundary(start)
+        && content.is_char_boundary(end);
+    if !in_range {
+        return FsResult::err("Selection is out of range");
+    }
+    let selection = trimmed(&content, start..end);
+    if selection.is_empty() {
+        return FsResult::err("Selection is empty");
+    }
+    if frontmatter::parse_frontmatter(&content).is_some_and(|fm| selection.start < fm.body_start) {
+        return FsResult::err("Selection overlaps the frontmatter");
+    }
+
+    if new_name.trim().is_empty() {
+        return FsResult::err("Note name cannot be empty");
+    }
+    let name = note_name(&new_name);
+    let dest = note_folder(&note_path).join(format!("{}.md", name));
+    if dest.exists() {
+        return FsResult::err("A note with this name already exists");
+    }
+
+    plan.copy(source, 0..selection.start, &note_path);
+    let link = format_wiki_link(&plan.link_target(&dest), None, None);
+    plan.output(&note_path).push_str(&link);
+    plan.copy(source, selection.end..content.len(), &note_path);
+    plan.copy(source, selection, &dest);
+
+    let label = format!(
+        "Extract \"{}\" from \"{}\"",
+        name,
+        index.name(plan.relocations[source].note)
+    );
+    plan.apply(&vault, label)
+}
+
+/// Merge notes into `target`, deleting the merged notes - Tauri command
+///
+/// `target` may be one of `paths` or a new note. Its content comes first,
+/// then each other note under a `# Note name` heading, without its
+/// frontmatter. Links to a merged note lead to its heading in the target,
+/// and links to its headings and blocks follow them.
+#[tauri::command]
+pub fn merge_notes(
+    vault_path: String,
+    paths: Vec<String>,
+    target: String,
+) -> FsResult<RefactorResult> {
+    let vault = PathBuf::from(&vault_path);
+    let target_path = PathBuf::from(&target);
+
+    if target_path.extension().is_none_or(|ext| ext != "md") {
+        return FsResult::err("Target must be a .md file");
+    }
+    let mut sources: Vec<PathBuf> = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path != target_path && !sources.contains(&path) {
+            sources.push(path);
+        }
+    }
+    if sources.is_empty() {
+        return FsResult::err("No other notes to merge");
+    }
+
+    let index = NoteIndex::build(&vault);
+    let mut plan = Plan::new(&index);
+
+    if index.position(&target_path).is_some() {
+        let own = match plan.relocate(&target_path) {
+            Ok(r) => r,
+            Err(e) => return FsResult::err(&e),
+        };
+        let len = plan.relocations[own].content.len();
+        plan.copy(own, 0..len, &target_path);
+    } else if target_path.exists() {
+        return FsResult::err("Target is not a note in this vault");
+    } else if !note_folder(&target_path).is_dir() {
+        return FsResult::err("Target folder does not exist");
+    }
+
+    for source in &sources {
+        let r = match plan.relocate(source) {
+            Ok(r) => r,
+            Err(e) => return FsResult::err(&format!("{}: {}", source.display(), e)),
+        };
+        let content = &plan.relocations[r].content;
+        let body_start = frontmatter::parse_frontmatter(content).map_or(0, |fm| fm.body_start);
+        let body = trimmed(content, body_start..content.len());
+
+        let title = format!("# {}", index.name(plan.relocations[r].note));
+        let at = plan.paragraph(&target_path, &title);
+        plan.copy(r, body, &target_path);
+        plan.relocations[r].whole = (target_path.clone(), Some(at));
+        plan.removed.push(source.clone());
+    }
+
+    let name = target_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let label = format!("Merge {} notes into \"{}\"", sources.len(), name);
+    plan.apply(&vault, label)
+}
+
+/// Delete `others` and point every link to them at `keep` instead. Links to
+/// headings and blocks that `keep` also has keep their anchor.
+pub(crate) fn redirect_notes(
+    vault: &Path,
+    keep: &Path,
+    others: &[PathBuf],
+    label: String,
+) -> FsResult<RefactorResult> {
+    let index = NoteIndex::build(&vault.to_path_buf());
+    let mut plan = Plan::new(&index);
+
+    let own = match plan.relocate(keep) {
+        Ok(r) => r,
+        Err(e) => return FsResult::err(&e),
+    };
+    let len = plan.relocations[own].content.len();
+    plan.copy(own, 0..len, keep);
+
+    for other in others.iter().filter(|p| p.as_path() != keep) {
+        let r = match plan.relocate(other) {
+            Ok(r) => r,
+            Err(e) => return FsResult::err(&format!("{}: {}", other.display(), e)),
+        };
+        plan.relocations[r].whole = (keep.to_path_buf(), None);
+        plan.removed.push(other.clone());
+    }
+
+    plan.apply(vault, label)
+}
@@ -0,0 +1,228 @@
+//! Background scheduler that emits `task-due` events when tasks come due
+//!
+//! The scheduler checks the open vaults once a minute. Each open task is
+//! reported once per due date; desktop notifications are optional. Reported
+//! tasks are only remembered while the app runs, so tasks that are still due
+//! are reported again after a restart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::tasks::{query_tasks, Clock, SystemClock, TaskFilter, TaskItem};
+use crate::{save_config, AppState, FsResult};
+
+/// Event emitted to the frontend for each task that comes due
+pub const TASK_DUE_EVENT: &str = "task-due";
+
+/// Seconds between scheduler checks
+const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Payload of the `task-due` event
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskDueEvent {
+    pub task: TaskItem,
+    /// Due before today rather than today
+    pub overdue: bool,
+}
+
+/// Finds tasks that have come due, remembering which were already reported
+pub struct DueTaskScheduler<C: Clock> {
+    clock: C,
+    /// Tasks reported and still due, by path, text and due date (in memory
+    /// only)
+    notified: HashSet<String>,
+}
+
+impl<C: Clock> DueTaskScheduler<C> {
+    pub fn new(clock: C) -> Self {
+        DueTaskScheduler {
+            clock,
+            notified: HashSet::new(),
+        }
+    }
+
+    /// Open tasks in the given vaults that are due today or earlier and have
+    /// not been reported yet
+    pub fn check(&mut self, vault_paths: &[String]) -> Vec<TaskDueEvent> {
+        let today = self.clock.today().format("%Y-%m-%d").to_string();
+        let filter = TaskFilter {
+            status: Some("open".to_string()),
+            ..Default::default()
+        };
+
+        let mut still_due: HashSet<String> = HashSet::new();
+        let mut events = Vec::new();
+
+        for vault_path in vault_paths {
+            let tasks = query_tasks(vault_path.clone(), Some(filter.clone()))
+                .data
+                .unwrap_or_default();
+
+            for task in tasks {
+                let due = match &task.due {
+                    Some(due) if *due <= today => due.clone(),
+                    _ => continue,
+                };
+
+                // Identify tasks by content rather than line so edits above don't re-notify
+                let key = format!("{}\u{0}{}\u{0}{}", task.path, task.text, due);
+                if !self.notified.contains(&key) {
+                    events.push(TaskDueEvent {
+                        overdue: due < today,
+                        task,
+                    });
+                }
+                still_due.insert(key);
+            }
+        }
+
+        // Forget tasks that were completed or rescheduled
+        self.notified = still_due;
+        events
+    }
+}
+
+/// Start the background scheduler thread
+pub fn start_task_scheduler(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let mut scheduler = DueTaskScheduler::new(SystemClock);
+
+        loop {
+            let state = app_handle.state::<AppState>();
+            let vaults: Vec<String> = state
+                .open_vaults
+                .lock()
+                .map(|open| open.clone())
+                .unwrap_or_default();
+            let notify = state
+                .task_notifications
+                .lock()
+                .map(|enabled| *enabled)
+                .unwrap_or(false);
+
+            for event in scheduler.check(&vaults) {
+                let _ = app_handle.emit(TASK_DUE_EVENT, event.clone());
+
+                if notify {
+                    let title = if event.overdue {
+                        "Task overdue"
+                    } else {
+                        "Task due today"
+                    };
+                    let _ = app_handle
+                        .notification()
+                        .builder()
+                        .title(title)
+                        .body(format!("{} ({})", event.task.text, event.task.note_name))
+                        .show();
+                }
+            }
+
+            thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+        }
+    });
+}
+
+/// Get whether due tasks also raise desktop notifications - Tauri command
+#[tauri::command]
+pub fn get_task_notifications(app_handle: tauri::AppHandle) -> FsResult<bool> {
+    let state = app_handle.state::<AppState>();
+
+    if let Ok(enabled) = state.task_notifications.lock() {
+        return FsResult::ok(*enabled);
+    }
+
+    FsResult::ok(false)
+}
+
+/// Enable or disable desktop notifications for due tasks - Tauri command
+#[tauri::command]
+pub fn set_task_notifications(app_handle: tauri::AppHandle, enabled: bool) -> FsResult<()> {
+    let state = app_handle.state::<AppState>();
+
+    if let Ok(mut current) = state.task_notifications.lock() {
+        *current = enabled;
+    }
+
+    // Save configuration
+    let _ = save_config(&app_handle);
+
+    FsResult::ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Local, NaiveDate, TimeZone};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    /// Clock whose date the test sets
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<NaiveDate>>);
+
+    impl FakeClock {
+        fn set(&self, date: &str) {
+            *self.0.lock().unwrap() = date.parse().unwrap();
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Local> {
+            let date = *self.0.lock().unwrap();
+            Local
+                .from_local_datetime(&date.and_hms_opt(9, 0, 0).unwrap())
+                .unwrap()
+        }
+    }
+
+    fn temp_vault(name: &str) -> PathBuf {
+        let vault = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&vault);
+        fs::create_dir_all(&vault).unwrap();
+        vault
+    }
+
+    #[test]
+    fn reports_each_due_date_once() {
+        let vault = temp_vault("open-note-reminders");
+        let note = vault.join("Tasks.md");
+        let vaults = vec![vault.to_string_lossy().to_string()];
+        let clock = FakeClock(Arc::new(Mutex::new(NaiveDate::default())));
+        let mut scheduler = DueTaskScheduler::new(clock.clone());
+        fs::write(&note, "- [ ] Pay rent 📅 2026-05-02\n").unwrap();
+
+        // Not due yet
+        clock.set("2026-05-01");
+        assert!(scheduler.check(&vaults).is_empty());
+
+        // Reported on the due date
+        clock.set("2026-05-02");
+        let events = scheduler.check(&vaults);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].task.text, "Pay rent 📅 2026-05-02");
+        assert!(!events[0].overdue);
+
+        // Not repeated while still due, even once overdue
+        assert!(scheduler.check(&vaults).is_empty());
+        clock.set("2026-05-03");
+        assert!(scheduler.check(&vaults).is_empty());
+
+        // Reported again, as overdue, after the due date changes
+        fs::write(&note, "- [ ] Pay rent 📅 2026-05-01\n").unwrap();
+        let events = scheduler.check(&vaults);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].overdue);
+
+        // Not reported once done
+        fs::write(&note, "- [x] Pay rent 📅 2026-05-01\n").unwrap();
+        assert!(scheduler.check(&vaults).is_empty());
+
+        let _ = fs::remove_dir_all(&vault);
+    }
+}
@@ -0,0 +1,496 @@
+//! Notes with the same or nearly the same content: exact copies by content
+//! hash and near-duplicates by MinHash over word shingles
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use crate::refactor::{redirect_notes, RefactorResult};
+use crate::{frontmatter, get_modified_time, parse_note_links, FsResult, NoteIndex};
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 5;
+
+/// MinHash bands and rows per band; a pair is compared exactly when all rows
+/// of some band agree (likely from a similarity of about 0.4 upwards)
+const LSH_BANDS: usize = 32;
+const LSH_ROWS: usize = 3;
+
+/// Default minimum similarity (0-1) for near-duplicates
+const DEFAULT_THRESHOLD: f64 = 0.7;
+
+/// Unchanged lines shown around each change in a diff preview
+const DIFF_CONTEXT: usize = 2;
+
+/// Lines in one diff preview
+const MAX_DIFF_LINES: usize = 200;
+
+/// Lines x lines above which a diff only trims the common start and end
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// How the notes in a cluster match
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    /// Identical content
+    Exact,
+    /// Mostly the same words in the same order
+    Near,
+}
+
+/// A note in a duplicate cluster
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateNote {
+    pub path: String,
+    pub relative_path: String,
+    pub name: String,
+    pub modified: u64,
+    pub words: usize,
+    /// Links from other notes to this one
+    pub inbound_links: usize,
+    /// Similarity to the first note of the cluster (0-1)
+    pub similarity: f64,
+}
+
+/// A line of a diff preview
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    /// Line in the first note (1-based), unless added
+    pub old_line: Option<usize>,
+    /// Line in the compared note (1-based), unless removed
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+/// Changes from the first note of a cluster to another
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteDiff {
+    pub path: String,
+    /// Changed lines with a little context
+    pub lines: Vec<DiffLine>,
+    /// Whether the preview was cut short
+    pub truncated: bool,
+}
+
+/// Notes that are copies of each other
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateCluster {
+    pub kind: DuplicateKind,
+    /// The notes; the first is the one suggested to keep (most linked, then
+    /// most recently modified)
+    pub notes: Vec<DuplicateNote>,
+    /// Lowest similarity of another note to the first
+    pub score: f64,
+    /// SHA-256 of the content of exact copies
+    pub hash: Option<String>,
+    /// Diff from the first note to each other note (near-duplicates only)
+    pub diffs: Vec<NoteDiff>,
+}
+
+/// A note's content and what it is compared by
+struct Candidate {
+    note: usize,
+    content: String,
+    hash: String,
+    shingles: HashSet<u64>,
+    words: usize,
+}
+
+fn hash_of<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes of the runs of `SHINGLE_WORDS` words in the note body, ignoring
+/// case and punctuation; returns them with the word count
+fn shingles(content: &str) -> (HashSet<u64>, usize) {
+    let body_start = frontmatter::parse_frontmatter(content).map_or(0, |fm| fm.body_start);
+    let words: Vec<String> = content[body_start..]
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let shingles = match words.len() {
+        0 => HashSet::new(),
+        n if n < SHINGLE_WORDS => HashSet::from([hash_of(&words)]),
+        _ => words.windows(SHINGLE_WORDS).map(hash_of).collect(),
+    };
+    (shingles, words.len())
+}
+
+fn minhash(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..LSH_BANDS * LSH_ROWS)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|s| hash_of((seed, s)))
+                .min()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Jaccard similarity of two shingle sets
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        1.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Line diff of two texts, with lines that are the same in both
+fn diff_lines(old: &str, new: &str) -> Vec<(DiffKind, Option<usize>, Option<usize>)> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops: Vec<(DiffKind, Option<usize>, Option<usize>)> = (0..prefix)
+        .map(|i| (DiffKind::Same, Some(i), Some(i)))
+        .collect();
+
+    if a_mid.len() * b_mid.len() <= MAX_DIFF_CELLS {
+        // Longest common subsequence, filled from the end
+        let width = b_mid.len() + 1;
+        let mut lcs = vec![0u32; (a_mid.len() + 1) * width];
+        for i in (0..a_mid.len()).rev() {
+            for j in (0..b_mid.len()).rev() {
+                lcs[i * width + j] = if a_mid[i] == b_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a_mid.len() || j < b_mid.len() {
+            if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+                ops.push((DiffKind::Same, Some(prefix + i), Some(prefix + j)));
+                i += 1;
+                j += 1;
+            } else if i < a_mid.len()
+                && (j == b_mid.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push((DiffKind::Removed, Some(prefix + i), None));
+                i += 1;
+            } else {
+                ops.push((DiffKind::Added, None, Some(prefix + j)));
+                j += 1;
+            }
+        }
+    } else {
+        ops.extend((0..a_mid.len()).map(|i| (DiffKind::Removed, Some(prefix + i), None)));
+        ops.extend((0..b_mid.len()).map(|j| (DiffKind::Added, None, Some(prefix + j))));
+    }
+
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+    ops.extend((0..suffix).map(|k| (DiffKind::Same, Some(a_end + k), Some(b_end + k))));
+    ops
+}
+
+/// Changed lines of a diff with `DIFF_CONTEXT` lines around them
+fn diff_preview(old: &str, new: &str, path: String) -> NoteDiff {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(old, new);
+
+    let changed: Vec<usize> = (0..ops.len())
+        .filter(|i| ops[*i].0 != DiffKind::Same)
+        .collect();
+    let near_change = |i: usize| {
+        let k = changed.partition_point(|c| *c + DIFF_CONTEXT < i);
+        changed.get(k).is_some_and(|c| *c <= i + DIFF_CONTEXT)
+    };
+
+    let mut lines = Vec::new();
+    let mut truncated = false;
+    for (i, (kind, old_line, new_line)) in ops.into_iter().enumerate() {
+        if !near_change(i) {
+            continue;
+        }
+        if lines.len() == MAX_DIFF_LINES {
+            truncated = true;
+            break;
+        }
+        let text = match new_line {
+            Some(j) => b[j],
+            None => old_line.map_or("", |i| a[i]),
+        };
+        lines.push(DiffLine {
+            kind,
+            old_line: old_line.map(|i| i + 1),
+            new_line: new_line.map(|j| j + 1),
+            text: text.to_string(),
+        });
+    }
+
+    NoteDiff {
+        path,
+        lines,
+        truncated,
+    }
+}
+
+/// Merge sets that share members (union-find over candidate indices)
+struct Clusters {
+    parent: Vec<usize>,
+}
+
+impl Clusters {
+    fn new(len: usize) -> Clusters {
+        Clusters {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn root(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+/// Find exact and near-duplicate notes - Tauri command
+///
+/// Exact copies have the same content (ignoring line endings and trailing
+/// whitespace). Near-duplicates share at least `threshold` (default 0.7) of
+/// their five-word shingles; candidates come from MinHash signatures, so
+/// thresholds below about 0.4 may miss pairs. Empty notes are skipped.
+#[tauri::command]
+pub fn find_duplicate_notes(
+    vault_path: String,
+    threshold: Option<f64>,
+) -> FsResult<Vec<DuplicateCluster>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::err("Vault does not exist");
+    }
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD).clamp(0.0, 1.0);
+
+    let index = NoteIndex::build(&vault);
+    let mut inbound = vec![0; index.notes.len()];
+    let mut candidates = Vec::new();
+
+    for (note, path) in index.notes.iter().enumerate() {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        for link in parse_note_links(&content) {
+            match index.resolve_link(&link, path) {
+                Some(target) if target != note => inbound[target] += 1,
+                _ => {}
+            }
+        }
+
+        let normalized = content.replace("\r\n", "\n");
+        let normalized = normalized.trim_end();
+        if normalized.trim().is_empty() {
+            continue;
+        }
+        let hash = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+        let (shingles, words) = shingles(normalized);
+        candidates.push(Candidate {
+            note,
+            content,
+            hash,
+            shingles,
+            words,
+        });
+    }
+
+    // The note to keep: most linked, then newest, then shortest path
+    let modified: Vec<u64> = index.notes.iter().map(get_modified_time).collect();
+    let keep_order = |c: &Candidate| {
+        (
+            std::cmp::Reverse(inbound[c.note]),
+            std::cmp::Reverse(modified[c.note]),
+            index.notes[c.note].components().count(),
+            index.notes[c.note].clone(),
+        )
+    };
+    candidates.sort_by_key(keep_order);
+
+    let mut by_hash: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        by_hash.entry(&candidate.hash).or_default().push(i);
+    }
+
+    // Near-duplicates: one note per set of exact copies, paired through
+    // MinHash bands, then checked with the exact shingle similarity
+    let representatives: Vec<usize> = by_hash
+        .values()
+        .map(|copies| copies[0])
+        .filter(|i| !candidates[*i].shingles.is_empty())
+        .collect();
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for &i in &representatives {
+        let signature = minhash(&candidates[i].shingles);
+        for (band, rows) in signature.chunks(LSH_ROWS).enumerate() {
+            buckets.entry((band, hash_of(rows))).or_default().push(i);
+        }
+    }
+
+    let mut clusters = Clusters::new(candidates.len());
+    let mut compared = HashSet::new();
+    for bucket in buckets.values().filter(|b| b.len() > 1) {
+        for (k, &a) in bucket.iter().enumerate() {
+            for &b in &bucket[k + 1..] {
+                if !compared.insert((a.min(b), a.max(b))) {
+                    continue;
+                }
+                if jaccard(&candidates[a].shingles, &candidates[b].shingles) >= threshold {
+                    clusters.join(a, b);
+                }
+            }
+        }
+    }
+
+    let note_info = |candidate: &Candidate, similarity: f64| DuplicateNote {
+        path: index.notes[candidate.note].to_string_lossy().to_string(),
+        relative_path: index.relative_path(candidate.note),
+        name: index.name(candidate.note),
+        modified: modified[candidate.note],
+        words: candidate.words,
+        inbound_links: inbound[candidate.note],
+        similarity,
+    };
+
+    let mut result = Vec::new();
+    for copies in by_hash.values().filter(|c| c.len() > 1) {
+        let first = &candidates[copies[0]];
+        result.push(DuplicateCluster {
+            kind: DuplicateKind::Exact,
+            notes: copies
+                .iter()
+                .map(|i| note_info(&candidates[*i], 1.0))
+                .collect(),
+            score: 1.0,
+            hash: Some(first.hash.clone()),
+            diffs: Vec::new(),
+        });
+    }
+
+    let mut near: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for &i in &representatives {
+        let root = clusters.root(i);
+        near.entry(root).or_default().push(i);
+    }
+    for members in near.values().filter(|m| m.len() > 1) {
+        // Every exact copy joins its representative; candidates are sorted,
+        // so the first is the note to keep
+        let mut notes: Vec<usize> = members
+            .iter()
+            .flat_map(|i| by_hash[candidates[*i].hash.as_str()].clone())
+            .collect();
+        notes.sort();
+
+        let keep = &candidates[notes[0]];
+        let similarity = |i: usize| match candidates[i].hash == keep.hash {
+            true => 1.0,
+            false => jaccard(&keep.shingles, &candidates[i].shingles),
+        };
+        let score = notes[1..]
+            .iter()
+            .map(|i| similarity(*i))
+            .fold(1.0, f64::min);
+        let diffs = notes[1..]
+            .iter()
+            .filter(|i| candidates[**i].hash != keep.hash)
+            .map(|i| {
+                let path = index.notes[candidates[*i].note]
+                    .to_string_lossy()
+                    .to_string();
+                diff_preview(&keep.content, &candidates[*i].content, path)
+            })
+            .collect();
+
+        result.push(DuplicateCluster {
+            kind: DuplicateKind::Near,
+            notes: notes
+                .iter()
+                .map(|i| note_info(&candidates[*i], similarity(*i)))
+                .collect(),
+            score,
+            hash: None,
+            diffs,
+        });
+    }
+
+    // Exact copies first, then the closest near-duplicates
+    result.sort_by(|a, b| {
+        (a.kind == DuplicateKind::Near)
+            .cmp(&(b.kind == DuplicateKind::Near))
+            .then_with(|| b.score.total_cmp(&a.score))
+            .then_with(|| b.notes.len().cmp(&a.notes.len()))
+            .then_with(|| a.notes[0].path.cmp(&b.notes[0].path))
+    });
+    FsResult::ok(result)
+}
+
+/// Keep one note of a duplicate cluster and delete the others - Tauri command
+///
+/// Links to the deleted notes are pointed at `keep`; links to their headings
+/// and blocks keep the anchor when `keep` has the same heading or block. The
+/// whole merge is one operation in the history.
+#[tauri::command]
+pub fn merge_duplicate_notes(
+    vault_path: String,
+    keep: String,
+    others: Vec<String>,
+) -> FsResult<RefactorResult> {
+    let vault = PathBuf::from(&vault_path);
+    let keep = PathBuf::from(&keep);
+    let others: Vec<PathBuf> = others
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| *p != keep)
+        .collect();
+
+    if others.is_empty() {
+        return FsResult::err("No other notes to merge");
+    }
+
+    let name = keep
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let label = format!("Merge {} duplicates into \"{}\"", others.len(), name);
+    redirect_notes(&vault, &keep, &others, label)
+}
@@ -0,0 +1,572 @@
+//! GFM task extraction and vault-wide task queries
+//!
+//! Recognised task metadata:
+//! - due dates: `📅 2026-10-20` or `due:2026-10-20`
+//! - priorities: `🔺` `⏫` `🔼` `🔽` `⏬` or `priority:high`
+//! - inline `#tags`
+//! - recurrence rules: `🔁 every week`, `🔁 every month on the 1st`
+
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::history::{vault_for, Journal};
+use crate::{collect_notes, fenced_line_mask, parse_inline_tags, FsResult};
+
+/// Task priority, highest first
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Highest,
+    High,
+    Medium,
+    Low,
+    Lowest,
+}
+
+/// A task item (`- [ ] text`) found in a note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskItem {
+    /// Path to the note containing the task
+    pub path: String,
+    /// Name of the note containing the task
+    pub note_name: String,
+    /// 1-based line number of the task in the note
+    pub line: usize,
+    /// Task text after the checkbox
+    pub text: String,
+    pub completed: bool,
+    /// Due date (YYYY-MM-DD)
+    pub due: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub tags: Vec<String>,
+    /// Recurrence rule text (e.g., "every week")
+    pub recurrence: Option<String>,
+    /// Indentation width of the list marker, for nested tasks
+    pub indent: usize,
+    /// The full source line
+    pub raw: String,
+}
+
+/// Result of toggling a task
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToggledTask {
+    /// The toggled task (its line moves down by one if an instance was inserted)
+    pub task: TaskItem,
+    /// Next instance created when a recurring task was completed
+    pub next_instance: Option<TaskItem>,
+}
+
+/// Source of the current time, injectable so date logic can be tested
+/// deterministically
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// Clock backed by the system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Day of the month a monthly rule lands on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonthDay {
+    Day(u32),
+    Last,
+}
+
+/// A parsed recurrence rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurrence {
+    Days(u32),
+    /// Every N weeks, optionally on specific weekdays
+    Weeks(u32, Vec<Weekday>),
+    /// Monday to Friday
+    Weekdays,
+    /// Every N months, optionally on a specific day
+    Months(u32, Option<MonthDay>),
+    Years(u32),
+}
+
+/// A recurrence rule plus whether it counts from the completion date
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub recurrence: Recurrence,
+    /// "when done": the next date is based on completion, not the due date
+    pub when_done: bool,
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name.trim().trim_end_matches('s') {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parse a weekday list such as "monday, wednesday and friday"
+fn parse_weekday_list(text: &str) -> Option<Vec<Weekday>> {
+    let days: Option<Vec<Weekday>> = text
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|w| !w.is_empty() && *w != "and")
+        .map(parse_weekday)
+        .collect();
+    days.filter(|d| !d.is_empty())
+}
+
+/// Parse "1st", "15th", "the 2nd" or "last" as a day of the month
+fn parse_month_day(text: &str) -> Option<MonthDay> {
+    let text = text.trim().trim_start_matches("the ").trim();
+    if text == "last" || text == "last day" {
+        return Some(MonthDay::Last);
+    }
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    match digits.parse::<u32>() {
+        Ok(d) if (1..=31).contains(&d) => Some(MonthDay::Day(d)),
+        _ => None,
+    }
+}
+
+impl RecurrenceRule {
+    /// Parse rule text such as "every 2 weeks on monday" or "every month on the 1st when done"
+    pub fn parse(text: &str) -> Option<RecurrenceRule> {
+        let text = text.trim().to_lowercase();
+        let (text, when_done) = match text.strip_suffix("when done") {
+            Some(rest) => (rest.trim().to_string(), true),
+            None => (text, false),
+        };
+        let rest = text.strip_prefix("every")?.trim();
+
+        if rest == "weekday" || rest == "weekdays" {
+            return Some(RecurrenceRule {
+                recurrence: Recurrence::Weekdays,
+                when_done,
+            });
+        }
+        if let Some(days) = parse_weekday_list(rest) {
+            return Some(RecurrenceRule {
+                recurrence: Recurrence::Weeks(1, days),
+                when_done,
+            });
+        }
+
+        static INTERVAL: OnceLock<Regex> = OnceLock::new();
+        let re = INTERVAL.get_or_init(|| {
+            Regex::new(r"^(?:(\d+)\s+)?(day|week|month|year)s?(?:\s+on\s+(.+))?$").unwrap()
+        });
+        let cap = re.captures(rest)?;
+        let interval = cap
+            .get(1)
+            .and_then(|m| m.as_str().parse::<u32>().ok())
+            .unwrap_or(1)
+            .max(1);
+        let on = cap.get(3).map(|m| m.as_str());
+
+        let recurrence = match (&cap[2], on) {
+            ("day", None) => Recurrence::Days(interval),
+            ("week", None) => Recurrence::Weeks(interval, Vec::new()),
+            ("week", Some(on)) => Recurrence::Weeks(interval, parse_weekday_list(on)?),
+            ("month", None) => Recurrence::Months(interval, None),
+            ("month", Some(on)) => Recurrence::Months(interval, Some(parse_month_day(on)?)),
+            ("year", None) => Recurrence::Years(interval),
+            _ => return None,
+        };
+
+        Some(RecurrenceRule {
+            recurrence,
+            when_done,
+        })
+    }
+
+    /// The first occurrence strictly after `base`
+    pub fn next_after(&self, base: NaiveDate) -> NaiveDate {
+        match &self.recurrence {
+            Recurrence::Days(n) => base + Duration::days(*n as i64),
+            Recurrence::Weeks(n, days) if days.is_empty() => base + Duration::weeks(*n as i64),
+            Recurrence::Weeks(n, days) => {
+                let mut date = base + Duration::days(1);
+                while !days.contains(&date.weekday()) {
+                    date += Duration::days(1);
+                }
+                // Wrapping into the next week skips the weeks in between
+                if *n > 1 && date.iso_week() != base.iso_week() {
+                    date += Duration::weeks(*n as i64 - 1);
+                }
+                date
+            }
+            Recurrence::Weekdays => {
+                let mut date = base + Duration::days(1);
+                while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                    date += Duration::days(1);
+                }
+                date
+            }
+            Recurrence::Months(n, None) => add_months(base, *n),
+            Recurrence::Months(n, Some(day)) => {
+                // Try the base month first, then every N months after it
+                let first = base.with_day(1).unwrap_or(base);
+                let mut step = 0;
+                loop {
+                    let month = add_months(first, step);
+                    let candidate = day_in_month(month, *day);
+                    if candidate > base {
+                        return candidate;
+                    }
+                    step += n;
+                }
+            }
+            Recurrence::Years(n) => add_months(base, n * 12),
+        }
+    }
+}
+
+/// Add calendar months, clamping to the end of shorter months
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    date.checked_add_months(Months::new(months)).unwrap_or(date)
+}
+
+/// The given day within the month of `month_start`, clamped to the month length
+fn day_in_month(month_start: NaiveDate, day: MonthDay) -> NaiveDate {
+    let last = add_months(month_start, 1) - Duration::days(1);
+    match day {
+        MonthDay::Last => last,
+        MonthDay::Day(d) => month_start.with_day(d).unwrap_or(last),
+    }
+}
+
+/// Filters for `query_tasks`; every field is optional
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TaskFilter {
+    /// "open", "done" or "all" (default)
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Only tasks due strictly before this date (YYYY-MM-DD)
+    #[serde(default)]
+    pub due_before: Option<String>,
+    /// Only tasks with this tag (nested tags such as "work/x" match "work")
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Only tasks in notes under this folder (relative to the vault root)
+    #[serde(default)]
+    pub folder: Option<String>,
+}
+
+/// Regex matching a GFM task list item, capturing indent, checkbox and text
+fn task_regex() -> &'static Regex {
+    static TASK: OnceLock<Regex> = OnceLock::new();
+    TASK.get_or_init(|| Regex::new(r"^(\s*)(?:[-*+]|\d+[.)])\s+\[([ xX])\](?:\s+(.*))?$").unwrap())
+}
+
+/// Regex matching a due date marker, capturing the date
+fn due_regex() -> &'static Regex {
+    static DUE: OnceLock<Regex> = OnceLock::new();
+    DUE.get_or_init(|| Regex::new(r"(?:📅\s*|\bdue::?\s*)(\d{4}-\d{2}-\d{2})").unwrap())
+}
+
+/// Extract the due date from task text
+fn parse_due(text: &str) -> Option<String> {
+    due_regex().captures(text).map(|cap| cap[1].to_string())
+}
+
+/// Extract the recurrence rule text from task text
+fn parse_recurrence(text: &str) -> Option<String> {
+    static RECURRENCE: OnceLock<Regex> = OnceLock::new();
+    let re = RECURRENCE.get_or_init(|| {
+        Regex::new(r"(?:🔁|\brepeat::?)\s*(every\b[^📅⏳🛫✅🔺⏫🔼🔽⏬#]*)").unwrap()
+    });
+    re.captures(text)
+        .map(|cap| cap[1].trim().to_string())
+        .filter(|rule| RecurrenceRule::parse(rule).is_some())
+}
+
+/// Extract the priority from task text
+fn parse_priority(text: &str) -> Option<TaskPriority> {
+    let emojis = [
+        ("🔺", TaskPriority::Highest),
+        ("⏫", TaskPriority::High),
+        ("🔼", TaskPriority::Medium),
+        ("🔽", TaskPriority::Low),
+        ("⏬", TaskPriority::Lowest),
+    ];
+    for (emoji, priority) in emojis {
+        if text.contains(emoji) {
+            return Some(priority);
+        }
+    }
+
+    static PRIORITY: OnceLock<Regex> = OnceLock::new();
+    let re = PRIORITY.get_or_init(|| {
+        Regex::new(r"(?i)\bpriority::?\s*(highest|high|medium|low|lowest)\b").unwrap()
+    });
+    re.captures(text)
+        .map(|cap| match cap[1].to_lowercase().as_str() {
+            "highest" => TaskPriority::Highest,
+            "high" => TaskPriority::High,
+            "medium" => TaskPriority::Medium,
+            "low" => TaskPriority::Low,
+            _ => TaskPriority::Lowest,
+        })
+}
+
+/// Parse the task on a single line, if it is one
+pub(crate) fn parse_task_line(
+    line: &str,
+    line_number: usize,
+    path: &str,
+    note_name: &str,
+) -> Option<TaskItem> {
+    let cap = task_regex().captures(line.trim_end_matches('\r'))?;
+    let text = cap.get(3).map_or("", |m| m.as_str()).trim().to_string();
+
+    Some(TaskItem {
+        path: path.to_string(),
+        note_name: note_name.to_string(),
+        line: line_number,
+        completed: &cap[2] != " ",
+        due: parse_due(&text),
+        priority: parse_priority(&text),
+        tags: parse_inline_tags(&text),
+        recurrence: parse_recurrence(&text),
+        indent: cap[1].len(),
+        raw: line.trim_end_matches('\r').to_string(),
+        text,
+    })
+}
+
+/// Parse every task in a note, skipping fenced code blocks
+pub(crate) fn parse_tasks(content: &str, path: &str, note_name: &str) -> Vec<TaskItem> {
+    let in_code = fenced_line_mask(content);
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !in_code.get(*i).copied().unwrap_or(false))
+        .filter_map(|(i, line)| parse_task_line(line, i + 1, path, note_name))
+        .collect()
+}
+
+/// Check whether a task passes the filter
+fn matches_filter(task: &TaskItem, filter: &TaskFilter) -> bool {
+    match filter.status.as_deref() {
+        Some("open") if task.completed => return false,
+        Some("done") if !task.completed => return false,
+        _ => {}
+    }
+
+    if let Some(before) = &filter.due_before {
+        match &task.due {
+            Some(due) if due < before => {}
+            _ => return false,
+        }
+    }
+
+    if let Some(tag) = &filter.tag {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        let has_tag = task.tags.iter().any(|t| {
+            let t = t.to_lowercase();
+            t == tag || t.starts_with(&format!("{}/", tag))
+        });
+        if !has_tag {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Query tasks across the vault - Tauri command
+#[tauri::command]
+pub fn query_tasks(vault_path: String, filter: Option<TaskFilter>) -> FsResult<Vec<TaskItem>> {
+    let vault = PathBuf::from(&vault_path);
+
+    if !vault.exists() {
+        return FsResult::ok(vec![]);
+    }
+
+    let filter = filter.unwrap_or_default();
+    let root = match filter.folder.as_deref().map(|f| f.trim_matches('/')) {
+        Some(f) if !f.is_empty() => vault.join(f),
+        _ => vault.clone(),
+    };
+
+    let mut tasks: Vec<TaskItem> = Vec::new();
+    for note_path in collect_notes(&root) {
+        if let Ok(content) = fs::read_to_string(&note_path) {
+            let path = note_path.to_string_lossy().to_string();
+            let note_name = note_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            tasks.extend(
+                parse_tasks(&content, &path, &note_name)
+                    .into_iter()
+                    .filter(|t| matches_filter(t, &filter)),
+            );
+        }
+    }
+
+    // Tasks with due dates first (earliest first), then by location
+    tasks.sort_by(|a, b| {
+        let due_a = a.due.as_deref().unwrap_or("9999-99-99");
+        let due_b = b.due.as_deref().unwrap_or("9999-99-99");
+        due_a
+            .cmp(due_b)
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+
+    FsResult::ok(tasks)
+}
+
+/// Byte offset where each line of `content` starts
+fn line_starts(content: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+    starts
+}
+
+/// Build the line for the next instance of a recurring task: unchecked, with
+/// the due date moved (or added) to `next_due`
+fn next_instance_line(line: &str, checkbox_start: usize, next_due: NaiveDate) -> String {
+    let mut next = String::with_capacity(line.len() + 16);
+    next.push_str(&line[..checkbox_start]);
+    next.push(' ');
+    next.push_str(&line[checkbox_start + 1..]);
+
+    let due = next_due.format("%Y-%m-%d").to_string();
+    match due_regex().captures(&next).and_then(|cap| cap.get(1)) {
+        Some(m) => format!("{}{}{}", &next[..m.start()], due, &next[m.end()..]),
+        None => format!("{} 📅 {}", next.trim_end(), due),
+    }
+}
+
+/// Toggle a task using `clock` for "today" (see `toggle_task`)
+pub(crate) fn toggle_task_with_clock(
+    path: &str,
+    line: usize,
+    expected: Option<&str>,
+    clock: &dyn Clock,
+) -> Result<ToggledTask, String> {
+    let path_buf = PathBuf::from(path);
+
+    let content =
+        fs::read_to_string(&path_buf).map_err(|e| format!("Failed to read note: {}", e))?;
+
+    let starts = line_starts(&content);
+    if line == 0 || line > starts.len() {
+        return Err("Line is out of range".to_string());
+    }
+    if fenced_line_mask(&content)
+        .get(line - 1)
+        .copied()
+        .unwrap_or(false)
+    {
+        return Err("Line is inside a code block".to_string());
+    }
+
+    let start = starts[line - 1];
+    let end = starts.get(line).map(|s| s - 1).unwrap_or(content.len());
+    let line_text = content[start..end].trim_end_matches('\r');
+
+    if let Some(expected) = expected {
+        if expected.trim_end_matches('\r') != line_text {
+            return Err("Task line has changed since it was read".to_string());
+        }
+    }
+
+    let cap = task_regex()
+        .captures(line_text)
+        .ok_or_else(|| "Line is not a task".to_string())?;
+    let note_name = path_buf
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let current = parse_task_line(line_text, line, path, &note_name)
+        .ok_or_else(|| "Line is not a task".to_string())?;
+
+    // Replace just the checkbox character, leaving the rest of the file untouched
+    let checkbox = cap.get(2).unwrap();
+    let completing = checkbox.as_str() == " ";
+    let new_mark = if completing { "x" } else { " " };
+    let offset = start + checkbox.start();
+
+    // Completing a recurring task inserts its next instance above it
+    let rule = current
+        .recurrence
+        .as_deref()
+        .and_then(RecurrenceRule::parse);
+    let next_line = match (&rule, completing) {
+        (Some(rule), true) => {
+            let due = current
+                .due
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            let base = match due {
+                Some(d) if !rule.when_done => d,
+                _ => clock.today(),
+            };
+            let next_due = rule.next_after(base);
+            Some(next_instance_line(line_text, checkbox.start(), next_due))
+        }
+        _ => None,
+    };
+
+    let line_ending = if content[start..end].ends_with('\r') {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut updated = String::with_capacity(content.len() + 64);
+    updated.push_str(&content[..start]);
+    if let Some(next) = &next_line {
+        updated.push_str(next);
+        updated.push_str(line_ending);
+    }
+    updated.push_str(&content[start..offset]);
+    updated.push_str(new_mark);
+    updated.push_str(&content[offset + 1..]);
+
+    let label = format!("Toggle task \"{}\"", current.text);
+    Journal::new(vault_for(None, &path_buf), label).finish_write(&path_buf, &content, &updated)?;
+
+    let task_line = if next_line.is_some() { line + 1 } else { line };
+    let mut toggled_text = line_text.to_string();
+    toggled_text.replace_range(checkbox.start()..checkbox.end(), new_mark);
+
+    Ok(ToggledTask {
+        task: parse_task_line(&toggled_text, task_line, path, &note_name)
+            .ok_or_else(|| "Failed to parse updated task".to_string())?,
+        next_instance: next_line.and_then(|l| parse_task_line(&l, line, path, &note_name)),
+    })
+}
+
+/// Flip the checkbox of the task on a line (1-based) - Tauri command
+///
+/// When `expected` is given the line must still read exactly that, so a stale
+/// line number never toggles the wrong task. Completing a recurring task
+/// inserts its next instance on the line above.
+#[tauri::command]
+pub fn toggle_task(path: String, line: usize, expected: Option<String>) -> FsResult<ToggledTask> {
+    match toggle_task_with_clock(&path, line, expected.as_deref(), &SystemClock) {
+        Ok(toggled) => FsResult::ok(toggled),
+        Err(e) => FsResult::err(&e),
+    }
+}
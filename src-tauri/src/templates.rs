@@ -0,0 +1,353 @@
+//! Note templates with placeholders, user variables and destination rules
+//!
+//! Templates are markdown files in the vault's `templates` folder. Supported
+//! placeholders: `{{title}}`, `{{date}}`, `{{date:YYYY-MM-DD}}`, `{{time}}`,
+//! `{{time:HH:mm}}`, `{{cursor}}` and `{{any_variable}}`. Template frontmatter
+//! may set `template-folder`, `template-filename` and `template-prompts`;
+//! those keys are stripped from the created note.
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::frontmatter::{parse_frontmatter, remove_frontmatter_keys};
+use crate::{normalize_path, write_note, FsResult, Note};
+
+/// Folder (relative to the vault root) holding the vault's templates
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Frontmatter key for the default destination folder
+const KEY_FOLDER: &str = "template-folder";
+/// Frontmatter key for the default filename pattern
+const KEY_FILENAME: &str = "template-filename";
+/// Frontmatter key listing variables the user should be prompted for
+const KEY_PROMPTS: &str = "template-prompts";
+
+/// Placeholders filled in by the backend rather than by the user
+const BUILTIN_PLACEHOLDERS: [&str; 4] = ["title", "date", "time", "cursor"];
+
+/// Information about a template file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateInfo {
+    /// Template name relative to the templates folder, without .md
+    pub name: String,
+    pub path: String,
+    /// Default destination folder from the template's frontmatter
+    pub folder: Option<String>,
+    /// Default filename pattern from the template's frontmatter
+    pub filename: Option<String>,
+    /// Variables the caller should ask the user for
+    pub variables: Vec<String>,
+}
+
+/// A note created from a template
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateNote {
+    pub note: Note,
+    /// Byte offset of the `{{cursor}}` placeholder in the note content
+    pub cursor: Option<usize>,
+}
+
+/// Values available while rendering a template
+pub(crate) struct TemplateContext<'a> {
+    pub title: &'a str,
+    pub now: DateTime<Local>,
+    pub vars: &'a HashMap<String, String>,
+}
+
+/// Convert a moment.js-style date format (e.g., "YYYY-MM-DD") to a chrono format
+pub(crate) fn moment_to_chrono(format: &str) -> String {
+    // Longest tokens first so "YYYY" wins over "YY"
+    const TOKENS: [(&str, &str); 26] = [
+        ("YYYY", "%Y"),
+        ("GGGG", "%G"),
+        ("gggg", "%G"),
+        ("MMMM", "%B"),
+        ("dddd", "%A"),
+        ("DDDD", "%j"),
+        ("MMM", "%b"),
+        ("ddd", "%a"),
+        ("YY", "%y"),
+        ("MM", "%m"),
+        ("DD", "%d"),
+        ("WW", "%V"),
+        ("ww", "%V"),
+        ("HH", "%H"),
+        ("hh", "%I"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+        ("ZZ", "%z"),
+        ("M", "%-m"),
+        ("D", "%-d"),
+        ("W", "%-V"),
+        ("w", "%-V"),
+        ("H", "%-H"),
+        ("h", "%-I"),
+        ("A", "%p"),
+        ("Z", "%:z"),
+    ];
+
+    let mut result = String::new();
+    let mut rest = format;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        // [literal text] is copied verbatim
+        if c == '[' {
+            if let Some(close) = rest.find(']') {
+                result.push_str(&rest[1..close].replace('%', "%%"));
+                rest = &rest[close + 1..];
+                continue;
+            }
+        }
+
+        for (token, replacement) in TOKENS {
+            if let Some(after) = rest.strip_prefix(token) {
+                result.push_str(replacement);
+                rest = after;
+                continue 'outer;
+            }
+        }
+
+        if c == '%' {
+            result.push_str("%%");
+        } else {
+            result.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    result
+}
+
+/// Format a date with a moment.js-style format string
+pub(crate) fn format_moment(date: &DateTime<Local>, format: &str) -> String {
+    date.format(&moment_to_chrono(format)).to_string()
+}
+
+/// Regex matching `{{name}}` and `{{name:argument}}` placeholders
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER
+        .get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_\- ]+?)\s*(?::([^}]*))?\}\}").unwrap())
+}
+
+/// Render template placeholders, returning the content and the cursor offset
+pub(crate) fn render_template(content: &str, ctx: &TemplateContext) -> (String, Option<usize>) {
+    let re = placeholder_regex();
+    let mut output = String::with_capacity(content.len());
+    let mut cursor = None;
+    let mut last = 0;
+
+    for cap in re.captures_iter(content) {
+        let full_match = cap.get(0).unwrap();
+        let name = cap.get(1).map_or("", |m| m.as_str());
+        let argument = cap
+            .get(2)
+            .map(|m| m.as_str().trim())
+            .filter(|a| !a.is_empty());
+
+        output.push_str(&content[last..full_match.start()]);
+        last = full_match.end();
+
+        match name.to_lowercase().as_str() {
+            "title" => output.push_str(ctx.title),
+            "date" => output.push_str(&format_moment(&ctx.now, argument.unwrap_or("YYYY-MM-DD"))),
+            "time" => output.push_str(&format_moment(&ctx.now, argument.unwrap_or("HH:mm"))),
+            "cursor" => {
+                if cursor.is_none() {
+                    cursor = Some(output.len());
+                }
+            }
+            _ => {
+                let value = ctx.vars.get(name).or_else(|| {
+                    ctx.vars
+                        .iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                        .map(|(_, v)| v)
+                });
+                match value {
+                    Some(v) => output.push_str(v),
+                    // Leave unknown placeholders in place so nothing is silently lost
+                    None => output.push_str(full_match.as_str()),
+                }
+            }
+        }
+    }
+
+    output.push_str(&content[last..]);
+    (output, cursor)
+}
+
+/// Replace characters that are not allowed in file names
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Locate a template file by name (with or without .md) in the vault.
+/// Names that lead outside the templates folder (e.g., "../note") match nothing.
+pub(crate) fn find_template(vault: &Path, template: &str) -> Option<PathBuf> {
+    let templates_dir = normalize_path(&vault.join(TEMPLATES_DIR));
+    let name = template.trim_end_matches(".md");
+    let path = normalize_path(&templates_dir.join(format!("{}.md", name)));
+    if path.starts_with(&templates_dir) && path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Strip template-only frontmatter keys and render the placeholders
+pub(crate) fn render_note_body(raw: &str, ctx: &TemplateContext) -> (String, Option<usize>) {
+    let body = remove_frontmatter_keys(raw, &[KEY_FOLDER, KEY_FILENAME, KEY_PROMPTS]);
+    render_template(&body, ctx)
+}
+
+/// Read template metadata from its content
+fn template_info(templates_dir: &Path, path: &Path, content: &str) -> TemplateInfo {
+    let fm = parse_frontmatter(content);
+    let get = |key: &str| {
+        fm.as_ref()
+            .and_then(|fm| fm.get(key))
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .filter(|s| !s.is_empty())
+    };
+
+    // Declared prompts first, then any other user placeholders in the body
+    let mut variables: Vec<String> = fm
+        .as_ref()
+        .and_then(|fm| fm.get(KEY_PROMPTS))
+        .map(|v| v.as_list())
+        .unwrap_or_default();
+    for cap in placeholder_regex().captures_iter(content) {
+        let name = cap.get(1).map_or("", |m| m.as_str()).to_string();
+        if !BUILTIN_PLACEHOLDERS.contains(&name.to_lowercase().as_str())
+            && !variables.iter().any(|v| v.eq_ignore_ascii_case(&name))
+        {
+            variables.push(name);
+        }
+    }
+
+    let relative = path.strip_prefix(templates_dir).unwrap_or(path);
+    TemplateInfo {
+        name: relative
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/"),
+        path: path.to_string_lossy().to_string(),
+        folder: get(KEY_FOLDER),
+        filename: get(KEY_FILENAME),
+        variables,
+    }
+}
+
+/// List templates in the vault's templates folder - Tauri command
+#[tauri::command]
+pub fn list_templates(vault_path: String) -> FsResult<Vec<TemplateInfo>> {
+    let templates_dir = PathBuf::from(&vault_path).join(TEMPLATES_DIR);
+
+    if !templates_dir.exists() {
+        return FsResult::ok(vec![]);
+    }
+
+    let templates = crate::collect_vault_files(&templates_dir)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|p| {
+            let content = fs::read_to_string(&p).ok()?;
+            Some(template_info(&templates_dir, &p, &content))
+        })
+        .collect();
+
+    FsResult::ok(templates)
+}
+
+/// Create a note from a template - Tauri command
+///
+/// `name` and `folder` override the template's `template-filename` and
+/// `template-folder` frontmatter. Fails rather than overwriting an existing note
+/// or writing outside the vault.
+#[tauri::command]
+pub fn create_note_from_template(
+    vault_path: String,
+    template: String,
+    name: Option<String>,
+    folder: Option<String>,
+    vars: Option<HashMap<String, String>>,
+) -> FsResult<TemplateNote> {
+    let vault = PathBuf::from(&vault_path);
+
+    let template_path = match find_template(&vault, &template) {
+        Some(p) => p,
+        None => return FsResult::err("Template not found"),
+    };
+
+    let raw = match fs::read_to_string(&template_path) {
+        Ok(c) => c,
+        Err(e) => return FsResult::err(&format!("Failed to read template: {}", e)),
+    };
+
+    let info = template_info(&vault.join(TEMPLATES_DIR), &template_path, &raw);
+    let vars = vars.unwrap_or_default();
+    let now = Local::now();
+
+    // Work out the note name: explicit name, else the template's filename pattern
+    let name = match name.filter(|n| !n.trim().is_empty()) {
+        Some(n) => n,
+        None => match &info.filename {
+            Some(pattern) => {
+                let ctx = TemplateContext {
+                    title: "",
+                    now,
+                    vars: &vars,
+                };
+                render_template(pattern, &ctx).0
+            }
+            None => return FsResult::err("A note name is required for this template"),
+        },
+    };
+    let name = sanitize_file_name(&name);
+    if name.is_empty() {
+        return FsResult::err("A note name is required for this template");
+    }
+
+    let folder = folder
+        .or(info.folder)
+        .map(|f| f.trim_matches('/').to_string())
+        .filter(|f| !f.is_empty());
+
+    let target_dir = match &folder {
+        Some(f) => normalize_path(&vault.join(f)),
+        None => normalize_path(&vault),
+    };
+    // The folder may come from the template, so keep "../" and absolute paths in
+    if !target_dir.starts_with(normalize_path(&vault)) {
+        return FsResult::err("Folder must be inside the vault");
+    }
+    if target_dir.join(format!("{}.md", name)).exists() {
+        return FsResult::err("A note with this name already exists");
+    }
+
+    let ctx = TemplateContext {
+        title: &name,
+        now,
+        vars: &vars,
+    };
+    let (content, cursor) = render_note_body(&raw, &ctx);
+
+    let result = write_note(vault_path, name, content, folder);
+    match result.data {
+        Some(note) => FsResult::ok(TemplateNote { note, cursor }),
+        None => FsResult::err(result.error.as_deref().unwrap_or("Failed to write note")),
+    }
+}
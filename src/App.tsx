@@ -126,7 +126,7 @@ function App() {
     navigateToLink,
   } = useWikiLinks(
     currentVault?.path || null,
-    currentNote?.name || null,
+    currentNote?.path || null,
     editContent,
     {
       onOpenNote: (notePath: string) => {
@@ -408,7 +408,7 @@ export function useNotes() {
   const deleteNoteFn = useCallback(
     async (notePath: string) => {
       setError(null);
-      const result = await notesApi.deleteNote(notePath);
+      const result = await notesApi.deleteNote(notePath, currentVault?.path);
       if (result.success) {
         // Remove from open notes
         setOpenNotes(prev => {
@@ -434,7 +434,7 @@ export function useNotes() {
   const renameNoteFn = useCallback(
     async (oldPath: string, newName: string) => {
       setError(null);
-      const result = await notesApi.renameNote(oldPath, newName);
+      const result = await notesApi.renameNote(oldPath, newName, currentVault?.path);
       if (result.success && result.data) {
         // Update the note in openNotes
         setOpenNotes(prev => prev.map(n =>
@@ -10,7 +10,7 @@ interface WikiLinksCallbacks {
 
 export function useWikiLinks(
   vaultPath: string | null,
-  currentNoteName: string | null,
+  currentNotePath: string | null,
   content: string,
   callbacks: WikiLinksCallbacks
 ) {
@@ -53,10 +53,10 @@ export function useWikiLinks(
   }, []);
 
   // Load backlinks for current note
-  const loadBacklinks = useCallback(async (vault: string, noteName: string) => {
+  const loadBacklinks = useCallback(async (vault: string, notePath: string) => {
     setIsLoading(true);
     try {
-      const result = await notesApi.getBacklinks(vault, noteName);
+      const result = await notesApi.getBacklinks(vault, notePath);
       if (result.success && result.data) {
         setBacklinks(result.data);
       } else {
@@ -75,7 +75,11 @@ export function useWikiLinks(
       if (!vaultPath) return;
 
       try {
-        const result = await notesApi.resolveWikiLink(vaultPath, link.target);
+        const result = await notesApi.resolveWikiLink(
+          vaultPath,
+          link.target,
+          currentNotePath ?? undefined
+        );
         if (result.success && result.data) {
           // Note exists - open it
           callbacksRef.current.onOpenNote(result.data);
@@ -99,7 +103,7 @@ export function useWikiLinks(
         console.error("Failed to resolve wiki link:", error);
       }
     },
-    [vaultPath]
+    [vaultPath, currentNotePath]
   );
 
   // Parse links when content changes
@@ -117,10 +121,10 @@ export function useWikiLinks(
     // Clear backlinks immediately when note changes
     setBacklinks([]);
 
-    if (vaultPath && currentNoteName) {
-      loadBacklinks(vaultPath, currentNoteName);
+    if (vaultPath && currentNotePath) {
+      loadBacklinks(vaultPath, currentNotePath);
     }
-  }, [vaultPath, currentNoteName, loadBacklinks]);
+  }, [vaultPath, currentNotePath, loadBacklinks]);
 
   return {
     outgoingLinks,
@@ -125,15 +125,19 @@ export async function writeNote(
   return invoke<FsResult<Note>>("write_note", { vaultPath, name, content, folder });
 }
 
-export async function deleteNote(path: string): Promise<FsResult<void>> {
-  return invoke<FsResult<void>>("delete_note", { path });
+export async function deleteNote(
+  path: string,
+  vaultPath?: string
+): Promise<FsResult<void>> {
+  return invoke<FsResult<void>>("delete_note", { path, vaultPath });
 }
 
 export async function renameNote(
   path: string,
-  newName: string
+  newName: string,
+  vaultPath?: string
 ): Promise<FsResult<Note>> {
-  return invoke<FsResult<Note>>("rename_note", { path, newName });
+  return invoke<FsResult<Note>>("rename_note", { path, newName, vaultPath });
 }
 
 // Wiki Links
@@ -143,16 +147,21 @@ export async function parseLinks(content: string): Promise<ParsedLinks> {
 
 export async function getBacklinks(
   vaultPath: string,
-  noteName: string
+  notePath: string
 ): Promise<FsResult<BacklinkInfo[]>> {
-  return invoke<FsResult<BacklinkInfo[]>>("get_backlinks", { vaultPath, noteName });
+  return invoke<FsResult<BacklinkInfo[]>>("get_backlinks", { vaultPath, notePath });
 }
 
 export async function resolveWikiLink(
   vaultPath: string,
-  target: string
+  target: string,
+  sourcePath?: string
 ): Promise<FsResult<string | null>> {
-  return invoke<FsResult<string | null>>("resolve_wiki_link", { vaultPath, target });
+  return invoke<FsResult<string | null>>("resolve_wiki_link", {
+    vaultPath,
+    target,
+    sourcePath,
+  });
 }
 
 // Theme Management